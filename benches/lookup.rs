@@ -0,0 +1,61 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use mandarin_to_pinyin::{init_map, is_mapped, lookup_chars_vec, lookup_fn};
+
+const SAMPLE: &str = "天地玄黄宇宙洪荒日月盈昃辰宿列张寒来暑往秋收冬藏闰余成岁律吕调阳";
+
+fn large_input() -> Vec<char> {
+    SAMPLE.chars().cycle().take(100_000).collect()
+}
+
+fn bench_lookups(c: &mut Criterion) {
+    init_map(None).unwrap();
+    let input = large_input();
+
+    c.bench_function("lookup_chars_vec per char", |b| {
+        b.iter(|| {
+            for &ch in &input {
+                black_box(lookup_chars_vec(&[ch]).unwrap());
+            }
+        })
+    });
+
+    c.bench_function("lookup_chars_vec whole slice", |b| {
+        b.iter(|| black_box(lookup_chars_vec(&input).unwrap()))
+    });
+
+    c.bench_function("lookup_fn closure", |b| {
+        let lookup = lookup_fn().unwrap();
+        b.iter(|| {
+            for &ch in &input {
+                black_box(lookup(ch));
+            }
+        })
+    });
+
+    // Presence checks, on input mixing mapped and unmapped characters
+    let input: Vec<char> = input
+        .into_iter()
+        .zip("abc中文，".chars().cycle())
+        .flat_map(|(a, b)| [a, b])
+        .collect();
+
+    c.bench_function("presence via map lookup", |b| {
+        let lookup = lookup_fn().unwrap();
+        b.iter(|| {
+            for &ch in &input {
+                black_box(lookup(ch).is_some());
+            }
+        })
+    });
+
+    c.bench_function("presence via is_mapped bitset", |b| {
+        b.iter(|| {
+            for &ch in &input {
+                black_box(is_mapped(ch).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_lookups);
+criterion_main!(benches);
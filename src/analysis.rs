@@ -0,0 +1,101 @@
+use crate::syllables::{full_final, split_initial};
+use crate::tone::{ToneStyle, normalize_u, strip_tone, to_tone_style, tone_number};
+use crate::{PinyinError, pinyin_map};
+
+/// Every form of a character's first reading, as produced by [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyllableAnalysis {
+    pub source_char: char,
+    /// With tone marks, as stored: `"nǚ"`.
+    pub diacritic: String,
+    /// With a tone number and `v` for `ü`: `"nv3"`.
+    pub numbered: String,
+    /// Without tone: `"nü"`.
+    pub toneless: String,
+    /// 1 to 4, or 5 for the neutral tone.
+    pub tone: u8,
+    /// The initial consonant (`"zh"`, `"n"`, ...), or empty for syllables without one
+    /// (`"ai"`, and `"yao"` and `"wu"`, whose `y` and `w` only spell a glide).
+    pub initial: String,
+    /// The full final with `ü` made explicit and abbreviations restored: `"ü"` for 居 (`jū`),
+    /// `"iao"` for 要 (`yào`), `"uei"` for 对 (`duì`).
+    pub final_: String,
+}
+
+/// Analyzes the first reading of every character of `text` in one pass, for apps that need
+/// several forms of each syllable. Characters without a reading are skipped.
+pub fn analyze(text: &str) -> Result<Vec<SyllableAnalysis>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(text
+        .chars()
+        .filter_map(|c| {
+            let reading = map.get(&(c as u32))?.first()?;
+            Some(analyze_reading(c, reading))
+        })
+        .collect())
+}
+
+fn analyze_reading(source_char: char, reading: &str) -> SyllableAnalysis {
+    let toneless = strip_tone(reading);
+    let explicit_u = normalize_u(&toneless);
+    let (initial, rest) = split_initial(&explicit_u);
+    let final_ = full_final(initial, rest);
+    let initial = match initial {
+        "y" | "w" => "",
+        other => other,
+    };
+
+    SyllableAnalysis {
+        source_char,
+        diacritic: reading.to_string(),
+        numbered: to_tone_style(reading, ToneStyle::Numbered),
+        tone: tone_number(reading),
+        initial: initial.to_string(),
+        final_,
+        toneless,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_analyze() {
+        init_test_map();
+        let analysis = analyze("女a要").unwrap();
+        assert_eq!(
+            analysis[0],
+            SyllableAnalysis {
+                source_char: '女',
+                diacritic: "nǚ".to_string(),
+                numbered: "nv3".to_string(),
+                toneless: "nü".to_string(),
+                tone: 3,
+                initial: "n".to_string(),
+                final_: "ü".to_string(),
+            }
+        );
+        assert_eq!(analysis.len(), 2);
+        assert_eq!(analysis[1].source_char, '要');
+        assert_eq!(
+            (analysis[1].initial.as_str(), analysis[1].final_.as_str()),
+            ("", "iao")
+        );
+
+        let parts: Vec<(String, String)> = analyze("居对中儿")
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.initial, a.final_))
+            .collect();
+        let parts: Vec<(&str, &str)> = parts
+            .iter()
+            .map(|(i, f)| (i.as_str(), f.as_str()))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![("j", "ü"), ("d", "uei"), ("zh", "ong"), ("", "er")]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::tone::{render_syllable, strip_tone, tone_number};
+use crate::{PinyinError, ToneStyle, load_mapping};
+
+static COMPACT_UNICODE_TO_PINYIN: OnceLock<HashMap<u32, Vec<CompactReading>>> = OnceLock::new();
+
+/// A reading stored as a toneless ASCII syllable (`ü` written as `v`) plus its tone number,
+/// from which any [`ToneStyle`] can be rendered without parsing tone marks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactReading {
+    pub toneless: Box<str>,
+    /// 1 to 4, or 5 for the neutral tone.
+    pub tone: u8,
+}
+
+impl CompactReading {
+    /// Splits a reading written with tone marks or a tone number into its compact form.
+    pub fn from_pinyin(pinyin: &str) -> Self {
+        CompactReading {
+            toneless: strip_tone(pinyin).replace('ü', "v").into_boxed_str(),
+            tone: tone_number(pinyin),
+        }
+    }
+
+    /// Renders the reading in `style`.
+    pub fn render(&self, style: ToneStyle) -> String {
+        render_syllable(&self.toneless, self.tone, style)
+    }
+}
+
+/// Initializes the global compact map, an alternative to [`init_map`](crate::init_map) that
+/// stores each reading as a [`CompactReading`]. Loads `bytes` when given, otherwise the
+/// embedded default data (which requires the `default-data` feature). Query it with
+/// [`lookup_compact`].
+pub fn init_map_compact(bytes: Option<&[u8]>) -> Result<(), Box<dyn std::error::Error>> {
+    let unicode_mapping = load_mapping(bytes)?;
+    let compact = unicode_mapping
+        .mappings
+        .into_iter()
+        .map(|(codepoint, readings)| {
+            let readings = readings
+                .iter()
+                .map(|reading| CompactReading::from_pinyin(reading))
+                .collect();
+            (codepoint, readings)
+        })
+        .collect();
+
+    COMPACT_UNICODE_TO_PINYIN
+        .set(compact)
+        .map_err(|_| "failed to set compact mappings in OnceLock")?;
+
+    Ok(())
+}
+
+/// Looks up `c` in the compact map and renders its readings in `style`.
+pub fn lookup_compact(c: char, style: ToneStyle) -> Result<Option<Vec<String>>, PinyinError> {
+    let map = COMPACT_UNICODE_TO_PINYIN
+        .get()
+        .ok_or(PinyinError::NotInitialized("init_map_compact"))?;
+    Ok(map
+        .get(&(c as u32))
+        .map(|readings| readings.iter().map(|r| r.render(style)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        if COMPACT_UNICODE_TO_PINYIN.get().is_none() {
+            let _ = init_map_compact(None);
+        }
+    }
+
+    #[test]
+    fn test_compact_reading() {
+        let reading = CompactReading::from_pinyin("nǚ");
+        assert_eq!(&*reading.toneless, "nv");
+        assert_eq!(reading.tone, 3);
+        assert_eq!(reading.render(ToneStyle::Diacritic), "nǚ");
+
+        let reading = CompactReading::from_pinyin("ǹg");
+        assert_eq!(&*reading.toneless, "ng");
+        assert_eq!(reading.render(ToneStyle::Diacritic), "ǹg");
+    }
+
+    #[test]
+    fn test_lookup_compact() {
+        init();
+        assert_eq!(
+            lookup_compact('女', ToneStyle::Diacritic).unwrap(),
+            Some(vec!["nǚ".to_string()])
+        );
+        assert_eq!(
+            lookup_compact('女', ToneStyle::Numbered).unwrap(),
+            Some(vec!["nv3".to_string()])
+        );
+        assert_eq!(
+            lookup_compact('女', ToneStyle::Toneless).unwrap(),
+            Some(vec!["nü".to_string()])
+        );
+        assert_eq!(
+            lookup_compact('万', ToneStyle::Numbered).unwrap(),
+            Some(vec!["wan4".to_string(), "mo4".to_string()])
+        );
+        assert_eq!(
+            lookup_compact('吗', ToneStyle::Numbered).unwrap(),
+            Some(vec!["ma5".to_string()])
+        );
+        assert_eq!(lookup_compact('A', ToneStyle::Diacritic).unwrap(), None);
+    }
+}
@@ -0,0 +1,636 @@
+use std::collections::HashMap;
+
+use phf::phf_map;
+
+use crate::{PinyinError, ToneStyle, pinyin_map, to_tone_style};
+
+/// Spoken names of common CJK punctuation, used when
+/// [`ConvertConfig::spoken_punctuation`] is set.
+static PUNCTUATION_READINGS: phf::Map<char, &'static str> = phf_map! {
+    '。' => "jù hào",
+    '，' => "dòu hào",
+    '、' => "dùn hào",
+    '；' => "fēn hào",
+    '：' => "mào hào",
+    '？' => "wèn hào",
+    '！' => "gǎn tàn hào",
+    '…' => "shěng lüè hào",
+    '“' => "zuǒ yǐn hào",
+    '”' => "yòu yǐn hào",
+    '（' => "zuǒ kuò hào",
+    '）' => "yòu kuò hào",
+    '《' => "zuǒ shū míng hào",
+    '》' => "yòu shū míng hào",
+    '·' => "jiàn gé hào",
+};
+
+/// How [`convert`] writes 儿 after another syllable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErhuaMode {
+    /// 儿 is its own syllable: 花儿 -> `"huā ér"`.
+    #[default]
+    Separate,
+    /// 儿 merges into the syllable before it as an erhua `r`: 花儿 -> `"huār"` (`"huar1"` with
+    /// tone numbers). Whether a 儿 is really a suffix depends on the word (女儿 and 儿子 keep a
+    /// full `ér`), so this is for text where it is known to be used that way.
+    Merged,
+}
+
+/// Options controlling how [`convert`] renders text.
+///
+/// Build one with struct-update syntax over the defaults:
+/// `ConvertConfig { group_non_chinese: true, ..Default::default() }`.
+#[derive(Debug, Clone)]
+pub struct ConvertConfig {
+    /// Inserted between consecutive pinyin syllables.
+    pub separator: String,
+    /// How tones are written in the output. Defaults to tone marks, like the stored readings.
+    pub tone_style: ToneStyle,
+    /// When `false` (the default), every character is joined with `separator`, exactly like
+    /// [`to_pinyin_string`](crate::to_pinyin_string).
+    ///
+    /// When `true`, contiguous runs of unmapped characters are kept intact:
+    /// * a run is emitted verbatim, with no separator inside it;
+    /// * whitespace at the edges of a run is dropped, and a single space separates the run
+    ///   from neighbouring pinyin;
+    /// * a run that starts with closing punctuation (`,`, `。`, `！`, ...) attaches directly to
+    ///   the preceding syllable with no space, and a run that ends with opening punctuation
+    ///   (`(`, `「`, ...) attaches directly to the following one;
+    /// * a whitespace-only run between two syllables becomes a single space.
+    pub group_non_chinese: bool,
+    /// Folds full-width ASCII variants (U+FF01–U+FF5E, `！` through `～`, including `Ａ`–`Ｚ`,
+    /// `ａ`–`ｚ` and `０`–`９`) to their half-width forms before conversion, so `"Ａ"` renders as
+    /// `"A"`. Other full-width and half-width forms are left as they are.
+    pub fold_full_width: bool,
+    /// Reads the structural particles 的, 地 and 得 as neutral-tone `de`, their usual reading in
+    /// running text, instead of their first dictionary reading (e.g. 得 `dé`). Leave it off for
+    /// dictionary-faithful output.
+    pub neutral_de: bool,
+    /// Reads the sentence-final particles 吗, 呢, 吧, 啊, 嘛, 呀, 啦, 哇, 呗, 咯 and 么 in the
+    /// neutral tone (咯 as `lo`), whatever tone the data gives them. Leave it off for
+    /// dictionary-faithful output.
+    pub neutral_particles: bool,
+    /// Reads recognized punctuation aloud for TTS, emitting its spoken name as pinyin syllables
+    /// (`。` -> `jù hào`) instead of passing it through. A small table of common CJK punctuation
+    /// is built in; [`punctuation_readings`](Self::punctuation_readings) extends or overrides it.
+    pub spoken_punctuation: bool,
+    /// Spoken names used by [`spoken_punctuation`](Self::spoken_punctuation), as
+    /// space-separated syllables with tone marks or tone numbers. Entries take precedence over
+    /// the built-in table, so `'.'` can be added or `'。'` re-worded.
+    pub punctuation_readings: HashMap<char, String>,
+    /// Lowercases ASCII letters passed through unconverted, so mixed input like `"ABC你好"`
+    /// yields one normalized search key (`"abc ni hao"` with [`ToneStyle::Toneless`]). Pinyin
+    /// is always lowercase, as stored, so this only affects Latin text; it applies after
+    /// [`fold_full_width`](Self::fold_full_width), so `"Ａ"` becomes `"a"` when both are set.
+    pub lowercase_latin: bool,
+    /// Rejects input longer than this many bytes with [`PinyinError::InputTooLong`] before any
+    /// work is done, so services can bound the memory spent per request. `None` (the default)
+    /// accepts any length.
+    pub max_input_len: Option<usize>,
+    /// Whether 儿 following another syllable is written separately (the default) or merged
+    /// into it as an erhua `r`. A 儿 is always separate unless the syllable right before it is
+    /// another character's reading, so it never merges into spoken punctuation or another 儿.
+    pub erhua: ErhuaMode,
+}
+
+impl Default for ConvertConfig {
+    fn default() -> Self {
+        ConvertConfig {
+            separator: " ".to_string(),
+            tone_style: ToneStyle::Diacritic,
+            group_non_chinese: false,
+            fold_full_width: false,
+            neutral_de: false,
+            neutral_particles: false,
+            spoken_punctuation: false,
+            punctuation_readings: HashMap::new(),
+            lowercase_latin: false,
+            max_input_len: None,
+            erhua: ErhuaMode::Separate,
+        }
+    }
+}
+
+enum Piece {
+    Syllable(String),
+    Other(String),
+}
+
+/// Converts `text` to pinyin according to `config`, using the first reading of each character.
+pub fn convert(text: &str, config: &ConvertConfig) -> Result<String, PinyinError> {
+    if let Some(max) = config.max_input_len
+        && text.len() > max
+    {
+        return Err(PinyinError::InputTooLong {
+            len: text.len(),
+            max,
+        });
+    }
+    let map = pinyin_map()?;
+    let chars = text.chars().map(|c| {
+        let c = if config.fold_full_width {
+            fold_full_width(c)
+        } else {
+            c
+        };
+        if config.lowercase_latin {
+            c.to_ascii_lowercase()
+        } else {
+            c
+        }
+    });
+
+    let mut pieces = Vec::new();
+    let mut run = String::new();
+    // Whether the last piece is a character's reading that a following 儿 may merge into
+    let mut erhua_host = false;
+    for c in chars {
+        if let Some(spoken) = spoken_punctuation(config, c) {
+            if !run.is_empty() {
+                pieces.push(Piece::Other(std::mem::take(&mut run)));
+            }
+            for syllable in spoken.split_whitespace() {
+                pieces.push(Piece::Syllable(to_tone_style(syllable, config.tone_style)));
+            }
+            erhua_host = false;
+            continue;
+        }
+        if config.erhua == ErhuaMode::Merged
+            && c == '儿'
+            && erhua_host
+            && let Some(Piece::Syllable(previous)) = pieces.last_mut()
+        {
+            *previous = with_erhua(previous);
+            erhua_host = false;
+            continue;
+        }
+        match reading(map, config, c) {
+            Some(pinyin) => {
+                if !run.is_empty() {
+                    pieces.push(Piece::Other(std::mem::take(&mut run)));
+                }
+                pieces.push(Piece::Syllable(pinyin));
+                erhua_host = c != '儿';
+            }
+            None if config.group_non_chinese => {
+                run.push(c);
+                erhua_host = false;
+            }
+            None => {
+                pieces.push(Piece::Other(c.to_string()));
+                erhua_host = false;
+            }
+        }
+    }
+    if !run.is_empty() {
+        pieces.push(Piece::Other(run));
+    }
+
+    if config.group_non_chinese {
+        Ok(join_grouped(&pieces, &config.separator))
+    } else {
+        let parts: Vec<&str> = pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Syllable(s) | Piece::Other(s) => s.as_str(),
+            })
+            .collect();
+        Ok(parts.join(&config.separator))
+    }
+}
+
+/// Returns the reading `convert` uses for `c`, or `None` to pass the character through.
+fn reading(map: &HashMap<u32, Vec<String>>, config: &ConvertConfig, c: char) -> Option<String> {
+    let pinyin = if config.neutral_de && matches!(c, '的' | '地' | '得') {
+        "de"
+    } else if let Some(particle) = neutral_particle(c).filter(|_| config.neutral_particles) {
+        particle
+    } else {
+        map.get(&(c as u32)).and_then(|p_vec| p_vec.first())?
+    };
+    Some(render(pinyin, config.tone_style))
+}
+
+/// Appends the erhua `r` to a rendered syllable, before the tone number if it has one.
+fn with_erhua(syllable: &str) -> String {
+    match syllable.strip_suffix(|c: char| c.is_ascii_digit()) {
+        Some(letters) => format!("{}r{}", letters, &syllable[letters.len()..]),
+        None => format!("{}r", syllable),
+    }
+}
+
+fn neutral_particle(c: char) -> Option<&'static str> {
+    Some(match c {
+        '吗' | '嘛' => "ma",
+        '呢' => "ne",
+        '吧' => "ba",
+        '啊' => "a",
+        '呀' => "ya",
+        '啦' => "la",
+        '哇' => "wa",
+        '呗' => "bei",
+        '咯' => "lo",
+        '么' => "me",
+        _ => return None,
+    })
+}
+
+fn render(pinyin: &str, style: ToneStyle) -> String {
+    match style {
+        ToneStyle::Diacritic => pinyin.to_string(),
+        style => to_tone_style(pinyin, style),
+    }
+}
+
+/// Returns the spoken name `convert` reads for `c`, if `c` is punctuation it should read aloud.
+fn spoken_punctuation(config: &ConvertConfig, c: char) -> Option<&str> {
+    if !config.spoken_punctuation {
+        return None;
+    }
+    match config.punctuation_readings.get(&c) {
+        Some(spoken) => Some(spoken),
+        None => PUNCTUATION_READINGS.get(&c).copied(),
+    }
+}
+
+/// Like [`convert`], but first rejects a separator that would make the output ambiguous to
+/// parse back: one containing ASCII letters, or, when `tone_style` is
+/// [`ToneStyle::Numbered`], ASCII digits.
+pub fn to_pinyin_string_checked(text: &str, config: &ConvertConfig) -> Result<String, PinyinError> {
+    let separator = &config.separator;
+    let ambiguous = separator.chars().any(|c| {
+        c.is_ascii_alphabetic() || (config.tone_style == ToneStyle::Numbered && c.is_ascii_digit())
+    });
+    if ambiguous {
+        return Err(PinyinError::AmbiguousSeparator(separator.clone()));
+    }
+    convert(text, config)
+}
+
+fn fold_full_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn join_grouped(pieces: &[Piece], separator: &str) -> String {
+    let mut result = String::new();
+    // Whether the last emitted piece was a syllable; `None` until something is emitted.
+    let mut prev_syllable: Option<bool> = None;
+    let mut pending_space = false;
+    let mut attach_next = false;
+
+    for piece in pieces {
+        match piece {
+            Piece::Syllable(pinyin) => {
+                match prev_syllable {
+                    Some(true) if !pending_space => result.push_str(separator),
+                    Some(_) if !attach_next => result.push(' '),
+                    _ => {}
+                }
+                result.push_str(pinyin);
+                prev_syllable = Some(true);
+                pending_space = false;
+                attach_next = false;
+            }
+            Piece::Other(run) => {
+                let trimmed = run.trim();
+                if trimmed.is_empty() {
+                    pending_space = prev_syllable.is_some();
+                    continue;
+                }
+                if prev_syllable == Some(true) && !trimmed.starts_with(is_closing_punctuation) {
+                    result.push(' ');
+                }
+                result.push_str(trimmed);
+                prev_syllable = Some(false);
+                attach_next = trimmed.ends_with(is_opening_punctuation);
+            }
+        }
+    }
+    result
+}
+
+fn is_closing_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        ',' | '.'
+            | ';'
+            | ':'
+            | '!'
+            | '?'
+            | ')'
+            | ']'
+            | '}'
+            | '，'
+            | '。'
+            | '、'
+            | '；'
+            | '：'
+            | '！'
+            | '？'
+            | '）'
+            | '】'
+            | '》'
+            | '」'
+            | '』'
+    )
+}
+
+fn is_opening_punctuation(c: char) -> bool {
+    matches!(c, '(' | '[' | '{' | '（' | '【' | '《' | '「' | '『')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    fn grouped(separator: &str) -> ConvertConfig {
+        ConvertConfig {
+            separator: separator.to_string(),
+            group_non_chinese: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_convert_default_matches_to_pinyin_string() {
+        init_test_map();
+        let text = "Hello 你好, world";
+        assert_eq!(
+            convert(text, &ConvertConfig::default()).unwrap(),
+            crate::to_pinyin_string(text, " ").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_convert_grouped_non_chinese() {
+        init_test_map();
+        let config = grouped(" ");
+
+        // Latin in the middle, at the start and at the end
+        assert_eq!(convert("ABC你好DEF", &config).unwrap(), "ABC nǐ hǎo DEF");
+        assert_eq!(convert("ABC你好", &config).unwrap(), "ABC nǐ hǎo");
+        assert_eq!(convert("你好DEF", &config).unwrap(), "nǐ hǎo DEF");
+
+        // Whitespace at run edges is collapsed into the single boundary space
+        assert_eq!(
+            convert("  Hello 你好 world  ", &config).unwrap(),
+            "Hello nǐ hǎo world"
+        );
+        assert_eq!(convert("你 好", &config).unwrap(), "nǐ hǎo");
+
+        // Closing punctuation attaches to the preceding syllable
+        assert_eq!(convert("你好, world!", &config).unwrap(), "nǐ hǎo, world!");
+        assert_eq!(
+            convert("你好，世界！", &config).unwrap(),
+            "nǐ hǎo， shì jiè！"
+        );
+
+        // Opening punctuation attaches to the following syllable
+        assert_eq!(convert("(你好)", &config).unwrap(), "(nǐ hǎo)");
+        assert_eq!(
+            convert("他说「你好」", &config).unwrap(),
+            "tā shuō 「nǐ hǎo」"
+        );
+
+        // Other punctuation is separated like any other run
+        assert_eq!(convert("你好 - 世界", &config).unwrap(), "nǐ hǎo - shì jiè");
+
+        assert_eq!(convert("", &config).unwrap(), "");
+    }
+
+    #[test]
+    fn test_convert_grouped_custom_separator() {
+        init_test_map();
+        let config = grouped("-");
+        assert_eq!(convert("iPhone手机", &config).unwrap(), "iPhone shǒu-jī");
+        assert_eq!(convert("你 好", &config).unwrap(), "nǐ hǎo");
+    }
+
+    #[test]
+    fn test_convert_fold_full_width() {
+        init_test_map();
+        let text = "ＡＢＣ你好１２３！";
+
+        let config = ConvertConfig::default();
+        assert_eq!(
+            convert(text, &config).unwrap(),
+            "Ａ Ｂ Ｃ nǐ hǎo １ ２ ３ ！"
+        );
+
+        let config = ConvertConfig {
+            fold_full_width: true,
+            ..Default::default()
+        };
+        assert_eq!(convert(text, &config).unwrap(), "A B C nǐ hǎo 1 2 3 !");
+
+        let config = ConvertConfig {
+            fold_full_width: true,
+            group_non_chinese: true,
+            ..Default::default()
+        };
+        assert_eq!(convert(text, &config).unwrap(), "ABC nǐ hǎo 123!");
+        // Characters outside U+FF01–U+FF5E are untouched
+        assert_eq!(convert("ｶ　Ａ", &config).unwrap(), "ｶ　A");
+    }
+
+    #[test]
+    fn test_convert_neutral_de() {
+        init_test_map();
+        let text = "我的书写得好";
+
+        assert_eq!(
+            convert(text, &ConvertConfig::default()).unwrap(),
+            "wǒ de shū xiě dé hǎo"
+        );
+
+        let config = ConvertConfig {
+            neutral_de: true,
+            ..Default::default()
+        };
+        assert_eq!(convert("我的", &config).unwrap(), "wǒ de");
+        assert_eq!(convert(text, &config).unwrap(), "wǒ de shū xiě de hǎo");
+        assert_eq!(convert("慢慢地", &config).unwrap(), "màn màn de");
+    }
+
+    #[test]
+    fn test_convert_neutral_particles() {
+        init_test_map();
+        let config = ConvertConfig {
+            neutral_particles: true,
+            ..Default::default()
+        };
+        assert_eq!(convert("你好吗", &config).unwrap(), "nǐ hǎo ma");
+        assert_eq!(
+            convert("好咯", &ConvertConfig::default()).unwrap(),
+            "hǎo gē"
+        );
+        assert_eq!(convert("好咯", &config).unwrap(), "hǎo lo");
+
+        let config = ConvertConfig {
+            tone_style: ToneStyle::Numbered,
+            ..config
+        };
+        assert_eq!(convert("你好吗", &config).unwrap(), "ni3 hao3 ma5");
+    }
+
+    #[test]
+    fn test_convert_tone_style() {
+        init_test_map();
+        let config = ConvertConfig {
+            tone_style: ToneStyle::Numbered,
+            neutral_de: true,
+            ..Default::default()
+        };
+        assert_eq!(convert("我的女儿", &config).unwrap(), "wo3 de5 nv3 er2");
+
+        let config = ConvertConfig {
+            tone_style: ToneStyle::Toneless,
+            ..Default::default()
+        };
+        assert_eq!(convert("我的女儿", &config).unwrap(), "wo de nü er");
+    }
+
+    #[test]
+    fn test_to_pinyin_string_checked() {
+        init_test_map();
+        let numbered = |separator: &str| ConvertConfig {
+            separator: separator.to_string(),
+            tone_style: ToneStyle::Numbered,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            to_pinyin_string_checked("你好", &numbered("-")).unwrap(),
+            "ni3-hao3"
+        );
+        assert_eq!(
+            to_pinyin_string_checked("你好", &numbered("1")),
+            Err(PinyinError::AmbiguousSeparator("1".to_string()))
+        );
+        assert_eq!(
+            to_pinyin_string_checked("你好", &numbered(" x ")),
+            Err(PinyinError::AmbiguousSeparator(" x ".to_string()))
+        );
+
+        // Digits are only ambiguous when tones are written as numbers
+        let diacritic = ConvertConfig {
+            separator: "1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            to_pinyin_string_checked("你好", &diacritic).unwrap(),
+            "nǐ1hǎo"
+        );
+    }
+
+    #[test]
+    fn test_convert_spoken_punctuation() {
+        init_test_map();
+        let text = "你好。再见！";
+        assert_eq!(
+            convert(text, &ConvertConfig::default()).unwrap(),
+            "nǐ hǎo 。 zài jiàn ！"
+        );
+
+        let config = ConvertConfig {
+            spoken_punctuation: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            convert(text, &config).unwrap(),
+            "nǐ hǎo jù hào zài jiàn gǎn tàn hào"
+        );
+        // Punctuation outside the table still passes through
+        assert_eq!(convert("你好.", &config).unwrap(), "nǐ hǎo .");
+
+        let config = ConvertConfig {
+            spoken_punctuation: true,
+            punctuation_readings: HashMap::from([
+                ('.', "jù hào".to_string()),
+                ('。', "ju4 dian3".to_string()),
+            ]),
+            tone_style: ToneStyle::Numbered,
+            group_non_chinese: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            convert("Hi.你好。", &config).unwrap(),
+            "Hi ju4 hao4 ni3 hao3 ju4 dian3"
+        );
+        // Numbered overrides are rendered with tone marks in the default style
+        let config = ConvertConfig {
+            tone_style: ToneStyle::Diacritic,
+            ..config
+        };
+        assert_eq!(convert("你好。", &config).unwrap(), "nǐ hǎo jù diǎn");
+    }
+
+    #[test]
+    fn test_convert_lowercase_latin() {
+        init_test_map();
+        let config = ConvertConfig {
+            tone_style: ToneStyle::Toneless,
+            group_non_chinese: true,
+            lowercase_latin: true,
+            ..Default::default()
+        };
+        assert_eq!(convert("ABC你好", &config).unwrap(), "abc ni hao");
+        assert_eq!(
+            convert("iPhone手机 É", &config).unwrap(),
+            "iphone shou ji É"
+        );
+
+        let config = ConvertConfig {
+            fold_full_width: true,
+            ..config
+        };
+        assert_eq!(convert("ＡＢＣ你好", &config).unwrap(), "abc ni hao");
+    }
+
+    #[test]
+    fn test_convert_max_input_len() {
+        init_test_map();
+        let config = ConvertConfig {
+            max_input_len: Some(6),
+            ..Default::default()
+        };
+        assert_eq!(convert("你好", &config).unwrap(), "nǐ hǎo");
+        assert_eq!(
+            convert("你好!", &config),
+            Err(PinyinError::InputTooLong { len: 7, max: 6 })
+        );
+    }
+
+    #[test]
+    fn test_convert_erhua() {
+        init_test_map();
+        let separate = ConvertConfig::default();
+        assert_eq!(convert("花儿", &separate).unwrap(), "huā ér");
+
+        let merged = ConvertConfig {
+            erhua: ErhuaMode::Merged,
+            ..Default::default()
+        };
+        assert_eq!(convert("一点儿", &merged).unwrap(), "yī diǎnr");
+        assert_eq!(convert("花儿开", &merged).unwrap(), "huār kāi");
+        // Nothing to merge into
+        assert_eq!(convert("儿", &merged).unwrap(), "ér");
+        assert_eq!(convert("a儿", &merged).unwrap(), "a ér");
+        assert_eq!(convert("儿儿", &merged).unwrap(), "ér ér");
+        let spoken = ConvertConfig {
+            spoken_punctuation: true,
+            ..merged.clone()
+        };
+        assert_eq!(convert("好。儿子", &spoken).unwrap(), "hǎo jù hào ér zi");
+
+        let numbered = ConvertConfig {
+            tone_style: ToneStyle::Numbered,
+            ..merged
+        };
+        assert_eq!(convert("花儿", &numbered).unwrap(), "huar1");
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+/// Converts text using its own maps instead of the global one, consulting an ordered chain of
+/// maps for each character: the first map with readings for it wins. This suits layered
+/// datasets, e.g. a small common-character map backed by a large rare-character map.
+#[derive(Debug, Clone, Default)]
+pub struct Converter {
+    maps: Vec<HashMap<u32, Vec<String>>>,
+}
+
+impl Converter {
+    /// Creates a converter that looks characters up in `maps`, in order.
+    pub fn with_fallback(maps: Vec<HashMap<u32, Vec<String>>>) -> Self {
+        Converter { maps }
+    }
+
+    /// Appends `map` to the end of the chain, so it is consulted only for characters no
+    /// earlier map has; for loading a supplementary map on demand.
+    pub fn push_fallback(&mut self, map: HashMap<u32, Vec<String>>) {
+        self.maps.push(map);
+    }
+
+    /// Returns the readings of `c` from the first map in the chain that has it.
+    pub fn lookup(&self, c: char) -> Option<&[String]> {
+        self.maps
+            .iter()
+            .find_map(|map| map.get(&(c as u32)))
+            .map(Vec::as_slice)
+    }
+
+    /// Like [`to_pinyin_string`](crate::to_pinyin_string), using the first reading found
+    /// through the chain of maps.
+    pub fn to_pinyin_string(&self, text: &str, separator: &str) -> String {
+        let pinyins: Vec<String> = text
+            .chars()
+            .map(|c| match self.lookup(c).and_then(|p_vec| p_vec.first()) {
+                Some(pinyin) => pinyin.clone(),
+                None => c.to_string(),
+            })
+            .collect();
+        pinyins.join(separator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converter_with_fallback() {
+        let common = HashMap::from([(0x4E2D, vec!["zhòng".to_string()])]);
+        let rare = HashMap::from([
+            (0x4E2D, vec!["zhōng".to_string()]),
+            (0x56FD, vec!["guó".to_string()]),
+        ]);
+        let mut converter = Converter::with_fallback(vec![common]);
+        assert_eq!(converter.to_pinyin_string("中国", " "), "zhòng 国");
+
+        converter.push_fallback(rare);
+        assert_eq!(converter.lookup('中'), Some(&["zhòng".to_string()][..]));
+        assert_eq!(converter.to_pinyin_string("中国a", " "), "zhòng guó a");
+        assert_eq!(converter.lookup('a'), None);
+    }
+}
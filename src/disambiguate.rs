@@ -0,0 +1,176 @@
+use crate::{PinyinError, is_chinese_char, pinyin_map};
+
+/// Where a polyphonic character occurs, passed to a [`Disambiguator`].
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    /// The whole input, as characters.
+    pub chars: &'a [char],
+    /// Index in `chars` of the character being resolved.
+    pub position: usize,
+}
+
+impl Context<'_> {
+    /// The character being resolved.
+    pub fn current(&self) -> char {
+        self.chars[self.position]
+    }
+
+    /// The character before the current one, if any.
+    pub fn previous(&self) -> Option<char> {
+        self.position.checked_sub(1).map(|index| self.chars[index])
+    }
+
+    /// The character after the current one, if any.
+    pub fn next(&self) -> Option<char> {
+        self.chars.get(self.position + 1).copied()
+    }
+}
+
+/// Chooses a reading for a character with several, so callers can plug in their own
+/// polyphone resolution (rules, statistics, a model) into [`to_pinyin_string_dyn`].
+pub trait Disambiguator {
+    /// Returns the reading to use, normally one of `readings` (which has at least two entries,
+    /// in the data's order).
+    fn pick<'a>(&self, context: &Context, readings: &'a [String]) -> &'a str;
+}
+
+/// The crate's default choice: the first reading, as in [`to_pinyin_string`](crate::to_pinyin_string).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstReading;
+
+impl Disambiguator for FirstReading {
+    fn pick<'a>(&self, _context: &Context, readings: &'a [String]) -> &'a str {
+        &readings[0]
+    }
+}
+
+/// Like [`to_pinyin_string`](crate::to_pinyin_string), but lets `disambiguator` choose the
+/// reading of every character that has more than one. Characters with a single reading use
+/// it, and characters without one pass through.
+pub fn to_pinyin_string_dyn(
+    text: &str,
+    separator: &str,
+    disambiguator: &dyn Disambiguator,
+) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let chars: Vec<char> = text.chars().collect();
+    let pinyins: Vec<String> = chars
+        .iter()
+        .enumerate()
+        .map(
+            |(position, &c)| match map.get(&(c as u32)).map(Vec::as_slice) {
+                Some([only]) => only.clone(),
+                Some(readings @ [_, _, ..]) => {
+                    let context = Context {
+                        chars: &chars,
+                        position,
+                    };
+                    disambiguator.pick(&context, readings).to_string()
+                }
+                _ => c.to_string(),
+            },
+        )
+        .collect();
+    Ok(pinyins.join(separator))
+}
+
+/// Like [`to_pinyin_string`](crate::to_pinyin_string), but with the reading of each character
+/// chosen in advance, e.g. by an external tagger: the i-th Chinese character (see
+/// [`is_chinese_char`](crate::is_chinese_char)) uses reading `indices[i]`, in the data's order.
+/// Every Chinese character consumes an index, even one the map has no reading for (it passes
+/// through), so indices line up with the tagger's count. An index past the last reading
+/// selects the last one; characters beyond the end of `indices` use their first reading.
+/// Other characters pass through without consuming an index.
+pub fn to_pinyin_with_indices(
+    text: &str,
+    indices: &[usize],
+    separator: &str,
+) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut indices = indices.iter();
+    let pinyins: Vec<String> = text
+        .chars()
+        .map(|c| {
+            let index = if is_chinese_char(c) {
+                indices.next().copied().unwrap_or(0)
+            } else {
+                0
+            };
+            let Some(readings) = map.get(&(c as u32)) else {
+                return c.to_string();
+            };
+            readings
+                .get(index)
+                .or(readings.last())
+                .map_or(c.to_string(), |p| p.clone())
+        })
+        .collect();
+    Ok(pinyins.join(separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    /// Reads 地 as `dì` when it follows 土.
+    struct SoilDi;
+
+    impl Disambiguator for SoilDi {
+        fn pick<'a>(&self, context: &Context, readings: &'a [String]) -> &'a str {
+            let wanted = match (context.previous(), context.current()) {
+                (Some('土'), '地') => "dì",
+                _ => return &readings[0],
+            };
+            readings
+                .iter()
+                .find(|reading| *reading == wanted)
+                .unwrap_or(&readings[0])
+        }
+    }
+
+    #[test]
+    fn test_to_pinyin_string_dyn() {
+        init_test_map();
+        let text = "土地的地a";
+        assert_eq!(
+            to_pinyin_string_dyn(text, " ", &FirstReading).unwrap(),
+            crate::to_pinyin_string(text, " ").unwrap()
+        );
+        assert_eq!(
+            to_pinyin_string_dyn(text, " ", &SoilDi).unwrap(),
+            "tǔ dì de de a"
+        );
+    }
+
+    #[test]
+    fn test_context_neighbours() {
+        let chars: Vec<char> = "你好".chars().collect();
+        let first = Context {
+            chars: &chars,
+            position: 0,
+        };
+        assert_eq!((first.previous(), first.next()), (None, Some('好')));
+        let last = Context {
+            position: 1,
+            ..first
+        };
+        assert_eq!((last.previous(), last.next()), (Some('你'), None));
+    }
+
+    #[test]
+    fn test_to_pinyin_with_indices() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_with_indices("土地a的地", &[0, 1, 0, 9], " ").unwrap(),
+            "tǔ dì a de dì"
+        );
+        // An unmapped ideograph still consumes its index
+        assert_eq!(
+            to_pinyin_with_indices("\u{F900}地", &[0, 1], " ").unwrap(),
+            "\u{F900} dì"
+        );
+        // Missing indices fall back to the first reading
+        assert_eq!(to_pinyin_with_indices("地地", &[], "").unwrap(), "dede");
+    }
+}
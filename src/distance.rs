@@ -0,0 +1,138 @@
+use crate::is_valid_syllable;
+use crate::syllables::split_initial;
+use crate::tone::strip_tone;
+
+/// Cost of inserting or deleting a whole syllable, and the most a substitution can cost.
+const SYLLABLE_COST: usize = 4;
+
+/// Initials that are commonly confused, as in the "fuzzy pinyin" options of input methods.
+const SIMILAR_INITIALS: &[(&str, &str)] = &[
+    ("zh", "z"),
+    ("ch", "c"),
+    ("sh", "s"),
+    ("n", "l"),
+    ("r", "l"),
+    ("f", "h"),
+];
+
+/// Finals that are commonly confused (front and back nasals).
+const SIMILAR_FINALS: &[(&str, &str)] = &[
+    ("an", "ang"),
+    ("en", "eng"),
+    ("in", "ing"),
+    ("ian", "iang"),
+    ("uan", "uang"),
+];
+
+/// Syllable-aware edit distance between two pinyin strings, for ranking "did you mean"
+/// suggestions. Syllables are separated by whitespace or apostrophes; tones are ignored.
+///
+/// The distance is a weighted Levenshtein distance over syllables:
+/// * inserting or deleting a syllable costs 4;
+/// * substituting one syllable for another costs the sum of an initial cost and a final
+///   cost, each 0 if equal, 1 if the pair is commonly confused (`zh`/`z`, `ch`/`c`, `sh`/`s`,
+///   `n`/`l`, `r`/`l`, `f`/`h`; `an`/`ang`, `en`/`eng`, `in`/`ing`, `ian`/`iang`, `uan`/`uang`)
+///   and 2 otherwise, so a substitution never costs more than a deletion and an insertion.
+///
+/// A part that is not a valid syllable only matches itself exactly. For plain character-level
+/// Levenshtein distance, use [`pinyin_distance_unweighted`].
+///
+/// ```
+/// use mandarin_to_pinyin::pinyin_distance;
+///
+/// assert_eq!(pinyin_distance("zhang san", "zang san"), 1);
+/// assert_eq!(pinyin_distance("zhang san", "wang san"), 2);
+/// assert_eq!(pinyin_distance("zhang san", "zhang"), 4);
+/// ```
+pub fn pinyin_distance(a: &str, b: &str) -> usize {
+    let a = syllables(a);
+    let b = syllables(b);
+    levenshtein(&a, &b, SYLLABLE_COST, |x, y| substitution_cost(x, y))
+}
+
+/// Character-level Levenshtein distance between two pinyin strings with tones removed, with
+/// every insertion, deletion and substitution costing 1.
+pub fn pinyin_distance_unweighted(a: &str, b: &str) -> usize {
+    let a: Vec<char> = toneless(a).chars().collect();
+    let b: Vec<char> = toneless(b).chars().collect();
+    levenshtein(&a, &b, 1, |x, y| usize::from(x != y))
+}
+
+fn toneless(text: &str) -> String {
+    text.split_whitespace()
+        .map(|part| strip_tone(&part.to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn syllables(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == '\'' || c == '’')
+        .filter(|part| !part.is_empty())
+        .map(|part| strip_tone(&part.to_lowercase()).replace('v', "ü"))
+        .collect()
+}
+
+fn substitution_cost(a: &str, b: &str) -> usize {
+    if a == b {
+        return 0;
+    }
+    if !is_valid_syllable(a) || !is_valid_syllable(b) {
+        return SYLLABLE_COST;
+    }
+    let (initial_a, final_a) = split_initial(a);
+    let (initial_b, final_b) = split_initial(b);
+    part_cost(initial_a, initial_b, SIMILAR_INITIALS) + part_cost(final_a, final_b, SIMILAR_FINALS)
+}
+
+fn part_cost(a: &str, b: &str, similar: &[(&str, &str)]) -> usize {
+    if a == b {
+        0
+    } else if similar
+        .iter()
+        .any(|&(x, y)| (a, b) == (x, y) || (a, b) == (y, x))
+    {
+        1
+    } else {
+        2
+    }
+}
+
+fn levenshtein<T>(a: &[T], b: &[T], indel: usize, substitute: impl Fn(&T, &T) -> usize) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).map(|j| j * indel).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![(i + 1) * indel];
+        for (j, y) in b.iter().enumerate() {
+            let cost = (previous[j] + substitute(x, y))
+                .min(previous[j + 1] + indel)
+                .min(current[j] + indel);
+            current.push(cost);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinyin_distance() {
+        assert_eq!(pinyin_distance("ni hao", "ni hao"), 0);
+        assert_eq!(pinyin_distance("nǐ hǎo", "ni3 hao"), 0);
+        assert_eq!(pinyin_distance("li", "ni"), 1);
+        assert_eq!(pinyin_distance("lin", "ling"), 1);
+        assert_eq!(pinyin_distance("zhang", "zan"), 2);
+        assert_eq!(pinyin_distance("ma", "ba"), 2);
+        assert_eq!(pinyin_distance("ma", "bo"), 4);
+        assert_eq!(pinyin_distance("xi'an", "xian"), 6);
+        assert_eq!(pinyin_distance("", "ni hao"), 8);
+    }
+
+    #[test]
+    fn test_pinyin_distance_unweighted() {
+        assert_eq!(pinyin_distance_unweighted("zhang", "zang"), 1);
+        assert_eq!(pinyin_distance_unweighted("nǐ hǎo", "ni hao"), 0);
+        assert_eq!(pinyin_distance_unweighted("ni hao", "nihao"), 1);
+    }
+}
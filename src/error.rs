@@ -0,0 +1,55 @@
+use std::fmt;
+
+/// Errors returned by the conversion and lookup functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinyinError {
+    /// The global map has not been loaded. The payload names the init function to call.
+    NotInitialized(&'static str),
+    /// The separator could be mistaken for part of a syllable in the chosen tone style.
+    AmbiguousSeparator(String),
+    /// The bytes are not exactly one character encoded as valid UTF-8.
+    InvalidUtf8Char(Vec<u8>),
+    /// The map could not be encoded to bincode.
+    Encode(String),
+    /// Map data could not be decoded. `len` is the length of the input in bytes.
+    Decode { len: usize, message: String },
+    /// The input is longer than the configured maximum. Both lengths are in bytes.
+    InputTooLong { len: usize, max: usize },
+}
+
+impl fmt::Display for PinyinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinyinError::NotInitialized(init_fn) => {
+                write!(f, "pinyin map not initialized. Call {} first.", init_fn)
+            }
+            PinyinError::AmbiguousSeparator(separator) => {
+                write!(
+                    f,
+                    "separator {:?} is ambiguous in the chosen tone style",
+                    separator
+                )
+            }
+            PinyinError::InvalidUtf8Char(bytes) => {
+                write!(
+                    f,
+                    "bytes {:02x?} are not exactly one UTF-8 encoded character",
+                    bytes
+                )
+            }
+            PinyinError::Encode(message) => write!(f, "failed to encode map: {}", message),
+            PinyinError::Decode { len, message } => {
+                write!(f, "failed to decode {} bytes of map data: {}", len, message)
+            }
+            PinyinError::InputTooLong { len, max } => {
+                write!(
+                    f,
+                    "input of {} bytes exceeds the maximum of {} bytes",
+                    len, max
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PinyinError {}
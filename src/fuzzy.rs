@@ -0,0 +1,199 @@
+use crate::reverse::reverse_index;
+use crate::syllables::split_initial;
+use crate::tone::{canonical_key, strip_tone, tone_number};
+use crate::{PinyinError, pinyin_map};
+
+/// Sound distinctions to ignore when matching syllables, as in the "fuzzy pinyin" settings of
+/// input methods. Each rule makes the pair on both sides match each other; all are off by
+/// default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FuzzyRules {
+    /// `zh` matches `z`.
+    pub zh_z: bool,
+    /// `ch` matches `c`.
+    pub ch_c: bool,
+    /// `sh` matches `s`.
+    pub sh_s: bool,
+    /// `n` matches `l`.
+    pub n_l: bool,
+    /// `r` matches `l`.
+    pub r_l: bool,
+    /// `f` matches `h`.
+    pub f_h: bool,
+    /// The final `an` matches `ang`.
+    pub an_ang: bool,
+    /// The final `en` matches `eng`.
+    pub en_eng: bool,
+    /// The final `in` matches `ing`.
+    pub in_ing: bool,
+    /// The final `ian` matches `iang`.
+    pub ian_iang: bool,
+    /// The final `uan` matches `uang`.
+    pub uan_uang: bool,
+}
+
+impl FuzzyRules {
+    /// Every rule enabled.
+    pub fn all() -> Self {
+        FuzzyRules {
+            zh_z: true,
+            ch_c: true,
+            sh_s: true,
+            n_l: true,
+            r_l: true,
+            f_h: true,
+            an_ang: true,
+            en_eng: true,
+            in_ing: true,
+            ian_iang: true,
+            uan_uang: true,
+        }
+    }
+
+    /// Rewrites a syllable (tone marks and numbers are dropped) to a canonical spelling, so two
+    /// syllables match under these rules exactly when their normalized forms are equal.
+    pub fn normalize(&self, syllable: &str) -> String {
+        let toneless = strip_tone(&syllable.to_lowercase()).replace('v', "ü");
+        let (initial, rest) = split_initial(&toneless);
+
+        let initial = match initial {
+            "zh" if self.zh_z => "z",
+            "ch" if self.ch_c => "c",
+            "sh" if self.sh_s => "s",
+            "r" if self.r_l && self.n_l => "n",
+            "r" if self.r_l => "l",
+            "l" if self.n_l => "n",
+            "f" if self.f_h => "h",
+            other => other,
+        };
+        let rest = match rest {
+            "ang" if self.an_ang => "an",
+            "eng" if self.en_eng => "en",
+            "ing" if self.in_ing => "in",
+            "iang" if self.ian_iang => "ian",
+            "uang" if self.uan_uang => "uan",
+            other => other,
+        };
+        format!("{}{}", initial, rest)
+    }
+}
+
+/// Reverse lookup tolerant of the distinctions `rules` ignores: returns, in codepoint order,
+/// every character with a reading that matches `syllable` after both are normalized with
+/// [`FuzzyRules::normalize`]. A query without a tone matches readings of any tone; a query
+/// with a tone mark or tone number only matches readings of that tone.
+pub fn fuzzy_lookup(syllable: &str, rules: FuzzyRules) -> Result<Vec<char>, PinyinError> {
+    let index = reverse_index()?;
+    let target = rules.normalize(syllable);
+    let has_tone = strip_tone(syllable) != syllable;
+    let tone = tone_number(syllable);
+
+    let mut chars: Vec<char> = index
+        .iter()
+        .filter(|(reading, _)| {
+            (!has_tone || tone_number(reading) == tone) && rules.normalize(reading) == target
+        })
+        .flat_map(|(_, chars)| chars.iter().copied())
+        .collect();
+    chars.sort_unstable();
+    chars.dedup();
+    Ok(chars)
+}
+
+/// Reduces `text` to a key on which phrases that sound alike collide, for deduplication. Each
+/// character with a reading contributes its first reading, normalized by:
+/// 1. lowercasing and removing the tone (`"Hǎo"` -> `"hao"`), so tones never distinguish;
+/// 2. writing every `ü` explicitly, including the `u` after `j`, `q`, `x` and `y` and the
+///    keyboard `v` (see [`canonical_key`](crate::canonical_key));
+/// 3. collapsing the initials and finals `rules` ignores (see [`FuzzyRules::normalize`]);
+///    with `FuzzyRules::default()` nothing is collapsed.
+///
+/// Runs of ASCII letters and digits are kept lowercased as their own tokens; punctuation,
+/// whitespace and other characters are dropped. Tokens are joined with single spaces, so
+/// 你好, 拟好！ and 你 号 share the fingerprint `"ni hao"`.
+pub fn phonetic_fingerprint(text: &str, rules: FuzzyRules) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut tokens: Vec<String> = Vec::new();
+    let mut latin = String::new();
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            latin.push(c.to_ascii_lowercase());
+            continue;
+        }
+        if !latin.is_empty() {
+            tokens.push(std::mem::take(&mut latin));
+        }
+        if let Some(pinyin) = map.get(&(c as u32)).and_then(|p_vec| p_vec.first()) {
+            tokens.push(rules.normalize(&canonical_key(pinyin)));
+        }
+    }
+    if !latin.is_empty() {
+        tokens.push(latin);
+    }
+    Ok(tokens.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{init_test_map, lookup_by_pinyin};
+
+    #[test]
+    fn test_fuzzy_normalize() {
+        let rules = FuzzyRules::all();
+        assert_eq!(rules.normalize("zhāng"), "zan");
+        assert_eq!(rules.normalize("lìng"), "nin");
+        assert_eq!(rules.normalize("nv3"), "nü");
+        assert_eq!(FuzzyRules::default().normalize("zhāng"), "zhang");
+    }
+
+    #[test]
+    fn test_fuzzy_lookup() {
+        init_test_map();
+        let rules = FuzzyRules {
+            zh_z: true,
+            ..Default::default()
+        };
+        let fuzzy = fuzzy_lookup("zong", rules).unwrap();
+        assert!(fuzzy.contains(&'中'));
+        assert!(fuzzy.contains(&'总'));
+        assert!(fuzzy.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // With a tone, only readings of that tone match
+        let first_tone = fuzzy_lookup("zōng", rules).unwrap();
+        assert!(first_tone.contains(&'中'));
+        assert!(!first_tone.contains(&'总'));
+
+        // Without rules a toned query is an exact reverse lookup
+        assert_eq!(
+            fuzzy_lookup("hǎo", FuzzyRules::default()).unwrap(),
+            lookup_by_pinyin("hǎo").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_phonetic_fingerprint() {
+        init_test_map();
+        let exact = FuzzyRules::default();
+        let key = phonetic_fingerprint("你好", exact).unwrap();
+        assert_eq!(key, "ni hao");
+        assert_eq!(phonetic_fingerprint("拟好！", exact).unwrap(), key);
+        assert_eq!(phonetic_fingerprint("你 号", exact).unwrap(), key);
+        assert_eq!(phonetic_fingerprint("居", exact).unwrap(), "jü");
+        assert_eq!(
+            phonetic_fingerprint("iPhone 15手机", exact).unwrap(),
+            "iphone 15 shou ji"
+        );
+
+        // 张 and 赞 only collide once zh/z and ang/an are collapsed
+        assert_ne!(
+            phonetic_fingerprint("张", exact).unwrap(),
+            phonetic_fingerprint("赞", exact).unwrap()
+        );
+        assert_eq!(
+            phonetic_fingerprint("张", FuzzyRules::all()).unwrap(),
+            phonetic_fingerprint("赞", FuzzyRules::all()).unwrap()
+        );
+    }
+}
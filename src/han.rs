@@ -0,0 +1,62 @@
+/// Returns `true` if `cp` is an assigned Han ideograph: any codepoint in the CJK Unified
+/// Ideographs blocks (including Extensions A–I), the CJK Compatibility Ideographs blocks, or
+/// the ideographic number zero `〇` (U+3007).
+pub(crate) fn is_cjk_ideograph(cp: u32) -> bool {
+    matches!(
+        cp,
+        0x3007
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xF900..=0xFA6D
+            | 0xFA70..=0xFAD9
+            | 0x20000..=0x2A6DF
+            | 0x2A700..=0x2B739
+            | 0x2B740..=0x2B81D
+            | 0x2B820..=0x2CEA1
+            | 0x2CEB0..=0x2EBE0
+            | 0x2EBF0..=0x2EE5D
+            | 0x2F800..=0x2FA1D
+            | 0x30000..=0x3134A
+            | 0x31350..=0x323AF
+    )
+}
+
+/// Returns `true` if `c` is a Han ideograph (the CJK Unified Ideographs blocks, their
+/// extensions, the compatibility ideographs and `〇`), whether or not the loaded map has a
+/// reading for it. Kanji and hanja are Han ideographs too; kana, hangul and CJK punctuation
+/// are not.
+pub fn is_chinese_char(c: char) -> bool {
+    is_cjk_ideograph(c as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{init_test_map, is_mapped, to_pinyin_string};
+
+    #[test]
+    fn test_kana_is_not_chinese() {
+        init_test_map();
+        // Hiragana, Katakana, Katakana Phonetic Extensions and half-width Katakana
+        let kana = (0x3041..=0x309F)
+            .chain(0x30A0..=0x30FF)
+            .chain(0x31F0..=0x31FF)
+            .chain(0xFF65..=0xFF9F)
+            .filter_map(char::from_u32);
+        for c in kana {
+            assert!(!is_chinese_char(c), "{:?} treated as Chinese", c);
+            assert!(!is_mapped(c).unwrap(), "{:?} has a reading", c);
+        }
+        assert!(is_chinese_char('東'));
+    }
+
+    #[test]
+    fn test_mixed_japanese_text() {
+        init_test_map();
+        // Kanji read as Mandarin, kana (including half-width) passed through verbatim
+        assert_eq!(
+            to_pinyin_string("東京へ行きますｶﾅ", " ").unwrap(),
+            "dōng jīng へ xíng き ま す ｶ ﾅ"
+        );
+    }
+}
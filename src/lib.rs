@@ -2,7 +2,64 @@ use bincode::{Decode, Encode};
 use phf::phf_map;
 use std::{collections::HashMap, sync::OnceLock};
 
+mod analysis;
+mod compact;
+mod convert;
+mod converter;
+mod disambiguate;
+mod distance;
+mod error;
+mod fuzzy;
+mod han;
+mod markup;
+mod number;
+mod reverse;
+mod sandhi;
+mod shared;
+mod stream;
+mod syllables;
+mod table;
+mod token;
+mod tone;
+mod words;
+mod yale;
+
+pub use analysis::{SyllableAnalysis, analyze};
+pub use compact::{CompactReading, init_map_compact, lookup_compact};
+pub use convert::{ConvertConfig, ErhuaMode, convert, to_pinyin_string_checked};
+pub use converter::Converter;
+pub use disambiguate::{
+    Context, Disambiguator, FirstReading, to_pinyin_string_dyn, to_pinyin_with_indices,
+};
+pub use distance::{pinyin_distance, pinyin_distance_unweighted};
+pub use error::PinyinError;
+pub use fuzzy::{FuzzyRules, fuzzy_lookup, phonetic_fingerprint};
+pub use han::is_chinese_char;
+pub use markup::{to_pinyin_preserving_tags, to_ssml};
+pub use number::number_reading;
+pub use reverse::{
+    canonical_reading, init_reading_frequencies, lookup_by_pinyin, lookup_by_pinyin_ranked,
+};
+pub use sandhi::{apply_a_sandhi, to_pinyin_sandhi_annotated, to_pinyin_string_sandhi};
+pub use shared::{lookup_shared, to_pinyin_shared};
+#[cfg(feature = "tokio")]
+pub use stream::convert_async_stream;
+pub use stream::{convert_srt, to_pinyin_jsonl};
+pub use syllables::{
+    SYLLABLES, SyllableTrie, complete_prefix, contract_final, expand_final, is_valid_syllable,
+    syllable_trie,
+};
+pub use table::to_aligned_table;
+pub use token::{Run, Token, pinyin_tokens, to_pinyin_runs};
+pub use tone::{
+    ToneStyle, canonical_key, normalize_u, place_tone, strip_tone, to_tone_style, tone_number,
+};
+pub use words::{init_word_map, longest_mapped_prefix, segment_words};
+pub use yale::pinyin_to_yale;
+
 static UNICODE_TO_PINYIN: OnceLock<HashMap<u32, Vec<String>>> = OnceLock::new();
+/// One bit per BMP codepoint, set if the global map has readings for it. Built on first use.
+static BMP_PRESENCE: OnceLock<Vec<u64>> = OnceLock::new();
 
 static DIACRITIC_TO_LETTER: phf::Map<char, &'static str> = phf_map! {
     'ā' => "a1",
@@ -74,6 +131,28 @@ static LETTER_TO_DIACRITIC: phf::Map<&'static str, char> = phf_map! {
     "n4" => 'ǹ'
 };
 
+/// Returns every tone-marked character [`diacritic_to_tone_plus_number`] converts, sorted by
+/// codepoint.
+pub fn supported_diacritics() -> &'static [char] {
+    static DIACRITICS: OnceLock<Vec<char>> = OnceLock::new();
+    DIACRITICS.get_or_init(|| {
+        let mut keys: Vec<char> = DIACRITIC_TO_LETTER.keys().copied().collect();
+        keys.sort_unstable();
+        keys
+    })
+}
+
+/// Returns every letter-plus-tone-number key (`"a1"`, `"v3"`, ...) that
+/// [`tone_plus_number_to_diacritic`] converts, sorted.
+pub fn supported_numbered_keys() -> &'static [&'static str] {
+    static KEYS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut keys: Vec<&'static str> = LETTER_TO_DIACRITIC.keys().copied().collect();
+        keys.sort_unstable();
+        keys
+    })
+}
+
 // The `Encode` and `Decode` traits are for bincode's native, high-performance serialization.
 // The `Serialize` and `Deserialize` traits are for serde-based formats like JSON.
 // We keep the serde traits for two reasons:
@@ -81,14 +160,60 @@ static LETTER_TO_DIACRITIC: phf::Map<&'static str, char> = phf_map! {
 // 2. Ecosystem compatibility: It's standard practice for data structures to be serde-compatible.
 #[derive(Encode, Decode, Debug)]
 pub struct UnicodeMapping {
+    /// Readings per codepoint. The order of each reading list is significant (most common
+    /// reading first) and is preserved exactly through `load_pinyin_map`, `save_to_vec`,
+    /// `load_from_bytes` and every lookup function.
     pub mappings: HashMap<u32, Vec<String>>,
 }
 
+impl From<HashMap<u32, Vec<String>>> for UnicodeMapping {
+    fn from(mappings: HashMap<u32, Vec<String>>) -> Self {
+        UnicodeMapping { mappings }
+    }
+}
+
+impl From<UnicodeMapping> for HashMap<u32, Vec<String>> {
+    fn from(mapping: UnicodeMapping) -> Self {
+        mapping.mappings
+    }
+}
+
+/// Returns a new mapping containing only the characters of `text` that `full` maps, with
+/// their readings. Useful for embedding a dataset that covers exactly an app's content.
+pub fn subset_map(full: &UnicodeMapping, text: &str) -> UnicodeMapping {
+    let mappings = text
+        .chars()
+        .filter_map(|c| {
+            let key = c as u32;
+            full.mappings
+                .get(&key)
+                .map(|readings| (key, readings.clone()))
+        })
+        .collect();
+    UnicodeMapping { mappings }
+}
+
 /// Deserializes a `UnicodeMapping` from a byte slice.
 /// This is the primary function for loading mapping data.
+///
+/// Fails with [`PinyinError::Decode`] if the data is malformed or truncated, or if bytes are
+/// left over after the mapping, which usually means files were concatenated.
 pub fn load_from_bytes(bytes: &[u8]) -> Result<UnicodeMapping, Box<dyn std::error::Error>> {
-    let (decoded, _len): (UnicodeMapping, usize) =
-        bincode::decode_from_slice(bytes, bincode::config::standard())?;
+    let decode_error = |message: String| PinyinError::Decode {
+        len: bytes.len(),
+        message,
+    };
+    let (decoded, len): (UnicodeMapping, usize) =
+        bincode::decode_from_slice(bytes, bincode::config::standard())
+            .map_err(|e| decode_error(e.to_string()))?;
+    if len < bytes.len() {
+        return Err(decode_error(format!(
+            "{} trailing bytes after the mapping ended at byte {}",
+            bytes.len() - len,
+            len
+        ))
+        .into());
+    }
     Ok(decoded)
 }
 
@@ -100,19 +225,30 @@ pub fn load_default() -> Result<UnicodeMapping, Box<dyn std::error::Error>> {
     load_from_bytes(bytes)
 }
 
-pub fn init_map(_bytes: Option<&[u8]>) -> Result<(), Box<dyn std::error::Error>> {
-    let unicode_mapping: UnicodeMapping;
+/// Returns the version stamp of the embedded default data, recorded by the `prepare-data`
+/// tool when it generated `unicode-to-pinyin.bin`.
+#[cfg(feature = "default-data")]
+pub fn default_data_version() -> &'static str {
+    include_str!("../bincode/unicode-to-pinyin.version").trim()
+}
 
-    #[cfg(feature = "default-data")]
-    {
-        unicode_mapping = load_default()?;
+/// Loads the mapping from `bytes`, or the embedded default data when `bytes` is `None`.
+pub(crate) fn load_mapping(
+    bytes: Option<&[u8]>,
+) -> Result<UnicodeMapping, Box<dyn std::error::Error>> {
+    match bytes {
+        Some(bytes) => load_from_bytes(bytes),
+        #[cfg(feature = "default-data")]
+        None => load_default(),
+        #[cfg(not(feature = "default-data"))]
+        None => Err("bytes is required but None was provided".into()),
     }
+}
 
-    #[cfg(not(feature = "default-data"))]
-    {
-        let bytes = _bytes.ok_or("bytes is required but None was provided")?;
-        unicode_mapping = load_from_bytes(bytes)?;
-    }
+/// Initializes the global map from `bytes`, or from the embedded default data when `bytes`
+/// is `None` (which requires the `default-data` feature).
+pub fn init_map(bytes: Option<&[u8]>) -> Result<(), Box<dyn std::error::Error>> {
+    let unicode_mapping = load_mapping(bytes)?;
 
     UNICODE_TO_PINYIN
         .set(unicode_mapping.mappings)
@@ -121,12 +257,109 @@ pub fn init_map(_bytes: Option<&[u8]>) -> Result<(), Box<dyn std::error::Error>>
     Ok(())
 }
 
+/// Like [`init_map`], but keeps only the characters in `start..=end` (codepoints), trimming
+/// resident memory for apps that know they only need, say, the URO block `0x4E00..=0x9FFF`.
+/// The full data is still decoded once; lookups outside the range then find nothing.
+pub fn init_map_range(
+    bytes: Option<&[u8]>,
+    start: u32,
+    end: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mappings = retain_range(load_mapping(bytes)?.mappings, start, end);
+
+    UNICODE_TO_PINYIN
+        .set(mappings)
+        .map_err(|_| "failed to set mappings in OneLock")?;
+
+    Ok(())
+}
+
+fn retain_range(
+    mut mappings: HashMap<u32, Vec<String>>,
+    start: u32,
+    end: u32,
+) -> HashMap<u32, Vec<String>> {
+    mappings.retain(|codepoint, _| (start..=end).contains(codepoint));
+    mappings.shrink_to_fit();
+    mappings
+}
+
+/// Returns the global map, or `PinyinError::NotInitialized` if `init_map` has not been called.
+pub(crate) fn pinyin_map() -> Result<&'static HashMap<u32, Vec<String>>, PinyinError> {
+    UNICODE_TO_PINYIN
+        .get()
+        .ok_or(PinyinError::NotInitialized("init_map"))
+}
+
+/// Encodes the currently loaded global map back to bincode bytes, in the format
+/// [`load_from_bytes`] and [`init_map`] read, so a service can persist the data it runs with.
+/// Codepoints are written in ascending order, so the same map always gives the same bytes.
+pub fn export_loaded_map() -> Result<Vec<u8>, PinyinError> {
+    let map = pinyin_map()?;
+    // A map encodes the same way whatever its type, so this decodes as a `UnicodeMapping`
+    let sorted: std::collections::BTreeMap<u32, &Vec<String>> = map
+        .iter()
+        .map(|(&codepoint, readings)| (codepoint, readings))
+        .collect();
+    bincode::encode_to_vec(&sorted, bincode::config::standard())
+        .map_err(|e| PinyinError::Encode(e.to_string()))
+}
+
+/// Initializes the global map with the default data, tolerating earlier initialization
+/// by another test.
+#[cfg(test)]
+pub(crate) fn init_test_map() {
+    if UNICODE_TO_PINYIN.get().is_none() {
+        let _ = init_map(None);
+    }
+}
+
 #[derive(Debug)]
 pub struct LookupResult<K> {
-    pub map: HashMap<K, Option<Vec<String>>>, 
+    pub map: HashMap<K, Option<Vec<String>>>,
     pub vec: Vec<Option<Vec<String>>>,
 }
 
+/// Returns a lookup closure that holds a reference to the global map, so tight loops can look
+/// up many characters without going through the `OnceLock` on every call.
+pub fn lookup_fn() -> Result<impl Fn(char) -> Option<&'static Vec<String>>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(move |c: char| map.get(&(c as u32)))
+}
+
+/// Looks up a single character given as UTF-8 bytes, for byte-oriented (e.g. FFI) callers.
+/// Returns [`PinyinError::InvalidUtf8Char`] unless `bytes` is exactly one valid UTF-8
+/// character.
+pub fn lookup_utf8(bytes: &[u8]) -> Result<Option<Vec<String>>, PinyinError> {
+    let map = pinyin_map()?;
+    let invalid = || PinyinError::InvalidUtf8Char(bytes.to_vec());
+    let text = std::str::from_utf8(bytes).map_err(|_| invalid())?;
+    let mut chars = text.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return Err(invalid());
+    };
+    Ok(map.get(&(c as u32)).cloned())
+}
+
+/// Returns `true` if the loaded map has readings for `c`. BMP characters are answered from a
+/// presence bitset built on first use, without hashing, which makes this a cheap precheck
+/// before a more expensive conversion.
+pub fn is_mapped(c: char) -> Result<bool, PinyinError> {
+    let map = pinyin_map()?;
+    let cp = c as u32;
+    if cp > 0xFFFF {
+        return Ok(map.contains_key(&cp));
+    }
+    let bits = BMP_PRESENCE.get_or_init(|| {
+        let mut bits = vec![0u64; 0x10000 / 64];
+        for &key in map.keys().filter(|&&key| key <= 0xFFFF) {
+            bits[key as usize / 64] |= 1 << (key % 64);
+        }
+        bits
+    });
+    Ok(bits[cp as usize / 64] & (1 << (cp % 64)) != 0)
+}
+
 pub fn lookup_unicodes_map(keys: &[u32]) -> Result<HashMap<u32, Option<Vec<String>>>, String> {
     let map = UNICODE_TO_PINYIN
         .get()
@@ -183,6 +416,50 @@ pub fn lookup_chars_vec(keys: &[char]) -> Result<Vec<Option<Vec<String>>>, Strin
         .collect())
 }
 
+/// What structured lookups report for a Chinese character the map has no reading for.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum UnmappedPlaceholder {
+    /// Report `None`, like [`lookup_chars_vec`].
+    #[default]
+    None,
+    /// Report this string as the only reading.
+    Text(String),
+    /// Report the character's codepoint as the only reading, e.g. `"U+F900"`.
+    CodepointEscape,
+}
+
+/// Options for [`lookup_chars_vec_with`].
+#[derive(Debug, Clone, Default)]
+pub struct LookupConfig {
+    /// Substituted for CJK ideographs without a reading, so pipelines can see exactly which
+    /// codepoints are missing. Other unmapped characters (Latin, punctuation) stay `None`.
+    pub unmapped_placeholder: UnmappedPlaceholder,
+}
+
+/// Like [`lookup_chars_vec`], but applies `config` to unmapped Chinese characters.
+pub fn lookup_chars_vec_with(
+    keys: &[char],
+    config: &LookupConfig,
+) -> Result<Vec<Option<Vec<String>>>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(keys
+        .iter()
+        .map(|&key| {
+            if let Some(readings) = map.get(&(key as u32)) {
+                return Some(readings.clone());
+            }
+            if !han::is_cjk_ideograph(key as u32) {
+                return None;
+            }
+            match &config.unmapped_placeholder {
+                UnmappedPlaceholder::None => None,
+                UnmappedPlaceholder::Text(text) => Some(vec![text.clone()]),
+                UnmappedPlaceholder::CodepointEscape => Some(vec![format!("U+{:04X}", key as u32)]),
+            }
+        })
+        .collect())
+}
+
 pub fn lookup_chars(keys: &[char]) -> Result<LookupResult<char>, String> {
     let map = UNICODE_TO_PINYIN
         .get()
@@ -223,16 +500,290 @@ pub fn to_pinyin_string(text: &str, separator: &str) -> Result<String, String> {
         .get()
         .ok_or("UNICODE_TO_PINYIN not initialized. Call init_map first.")?;
 
+    let mut result = String::with_capacity(estimated_pinyin_len(text, separator));
+    for (i, c) in text.chars().enumerate() {
+        if i > 0 {
+            result.push_str(separator);
+        }
+        match map.get(&(c as u32)).and_then(|p_vec| p_vec.first()) {
+            Some(pinyin) => result.push_str(pinyin),
+            None => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+/// The longest standard syllable in bytes, e.g. `"zhuàng"`: five ASCII letters and a two-byte
+/// marked vowel.
+const MAX_SYLLABLE_LEN: usize = 7;
+
+/// Estimates the length in bytes of [`to_pinyin_string`]'s output, for pre-allocating buffers.
+/// The estimate is an upper bound as long as every reading is a standard syllable, and is
+/// computed without looking anything up.
+pub fn estimated_pinyin_len(text: &str, separator: &str) -> usize {
+    let chars = text.chars().count();
+    chars * MAX_SYLLABLE_LEN + chars.saturating_sub(1) * separator.len()
+}
+
+/// Like [`to_pinyin_string`], but takes the characters directly, avoiding an intermediate
+/// `String` for callers that already hold a `&[char]`.
+pub fn chars_to_pinyin_string(chars: &[char], separator: &str) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let pinyins: Vec<String> = chars
+        .iter()
+        .map(|&c| first_pinyin_or_char(map, c))
+        .collect();
+    Ok(pinyins.join(separator))
+}
+
+/// Like [`to_pinyin_string`], but writes readings in decomposed (NFD) form for typesetting
+/// engines that shape combining marks themselves: every tone mark is a separate combining
+/// codepoint after its vowel (`"hǎo"` becomes `"ha\u{030C}o"`), and `ü` is `u` followed by
+/// U+0308. Characters without a reading are passed through unchanged.
+pub fn to_pinyin_string_decomposed(text: &str, separator: &str) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
     let pinyins: Vec<String> = text
+        .chars()
+        .map(
+            |c| match map.get(&(c as u32)).and_then(|p_vec| p_vec.first()) {
+                Some(pinyin) => tone::decompose(pinyin),
+                None => c.to_string(),
+            },
+        )
+        .collect();
+    Ok(pinyins.join(separator))
+}
+
+/// Like [`to_pinyin_string`], but for monospaced terminals: readings are written as ASCII
+/// with tone numbers (`"nv3"`, `"ma5"`), so no combining or precomposed marks can throw off
+/// alignment. With `pad`, every piece of the output (syllable or passed-through character) is
+/// padded with trailing spaces to the width of the longest one, giving fixed-width columns.
+pub fn to_pinyin_string_ascii_safe(
+    text: &str,
+    separator: &str,
+    pad: bool,
+) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut pieces: Vec<String> = text
+        .chars()
+        .map(
+            |c| match map.get(&(c as u32)).and_then(|p_vec| p_vec.first()) {
+                Some(pinyin) => to_tone_style(pinyin, ToneStyle::Numbered),
+                None => c.to_string(),
+            },
+        )
+        .collect();
+    if pad {
+        let width = pieces.iter().map(|p| p.chars().count()).max().unwrap_or(0);
+        for piece in &mut pieces {
+            let len = piece.chars().count();
+            piece.extend(std::iter::repeat_n(' ', width - len));
+        }
+    }
+    Ok(pieces.join(separator))
+}
+
+/// Like [`to_pinyin_string`], but keeps the output within `max_chars` characters for
+/// fixed-width labels. If the whole conversion does not fit, it stops before the first
+/// syllable that would overflow and appends `…`, which counts towards the limit; syllables are
+/// never cut. Returns the output and the number of source characters it covers.
+pub fn to_pinyin_string_truncated(
+    text: &str,
+    separator: &str,
+    max_chars: usize,
+) -> Result<(String, usize), PinyinError> {
+    const ELLIPSIS: char = '…';
+
+    let map = pinyin_map()?;
+    let separator_len = separator.chars().count();
+    let mut result = String::new();
+    // Byte offset and character count of the output after each converted character
+    let mut ends: Vec<(usize, usize)> = Vec::new();
+    let mut len = 0;
+
+    for c in text.chars() {
+        let piece = first_pinyin_or_char(map, c);
+        let added = piece.chars().count() + if ends.is_empty() { 0 } else { separator_len };
+        if len + added > max_chars {
+            // Drop whole syllables until the ellipsis fits
+            while len + 1 > max_chars {
+                if ends.pop().is_none() {
+                    return Ok((String::new(), 0));
+                }
+                (_, len) = ends.last().copied().unwrap_or((0, 0));
+            }
+            result.truncate(ends.last().map_or(0, |&(end, _)| end));
+            result.push(ELLIPSIS);
+            return Ok((result, ends.len()));
+        }
+        if !ends.is_empty() {
+            result.push_str(separator);
+        }
+        result.push_str(&piece);
+        len += added;
+        ends.push((result.len(), len));
+    }
+
+    Ok((result, ends.len()))
+}
+
+/// Writes the first reading of each Chinese character of `text` on its own line, for piping
+/// into `sort`, `uniq` and similar tools. Every line, including the last, ends with `\n`.
+/// Other characters are skipped, or, if `keep_other` is set, written on their own lines too
+/// (except whitespace, which is always skipped).
+pub fn to_pinyin_column(text: &str, keep_other: bool) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut result = String::with_capacity(estimated_pinyin_len(text, "\n"));
+    for c in text.chars() {
+        match map.get(&(c as u32)).and_then(|p_vec| p_vec.first()) {
+            Some(pinyin) => result.push_str(pinyin),
+            None if keep_other && !c.is_whitespace() => result.push(c),
+            None => continue,
+        }
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Converts vertical text stored one column per line, returning one vector per column with
+/// the first reading of each character (or the character itself if it has none). Lines are
+/// split as by [`str::lines`], so `\r\n` is handled and a trailing newline adds no column.
+pub fn to_pinyin_columns(text: &str) -> Result<Vec<Vec<String>>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(text
+        .lines()
+        .map(|column| {
+            column
+                .chars()
+                .map(|c| first_pinyin_or_char(map, c))
+                .collect()
+        })
+        .collect())
+}
+
+/// Pairs every character of `text` with its first reading (`None` if it has none), keeping a
+/// 1:1 correspondence with the source so timing tools can align per-character highlights.
+pub fn to_pinyin_timed(text: &str) -> Result<Vec<(char, Option<String>)>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(text
         .chars()
         .map(|c| {
-            map.get(&(c as u32))
-                .and_then(|p_vec| p_vec.get(0))
-                .map_or(c.to_string(), |p| p.clone())
+            let pinyin = map
+                .get(&(c as u32))
+                .and_then(|p_vec| p_vec.first())
+                .cloned();
+            (c, pinyin)
+        })
+        .collect())
+}
+
+/// Describes what the map holds for each Chinese character of `text`, one line per character
+/// in the form `"你(U+4F60): nǐ"`, with every reading in map order (`"地(U+5730): de dì"`).
+/// Han ideographs missing from the map are listed as `"(unmapped)"`; other characters are
+/// skipped. Meant for inspecting datasets, not for display.
+pub fn to_pinyin_debug(text: &str) -> Result<Vec<String>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(text
+        .chars()
+        .filter_map(|c| {
+            let readings = match map.get(&(c as u32)) {
+                Some(p_vec) => p_vec.join(" "),
+                None if han::is_chinese_char(c) => "(unmapped)".to_string(),
+                None => return None,
+            };
+            Some(format!("{}(U+{:04X}): {}", c, c as u32, readings))
+        })
+        .collect())
+}
+
+/// Counts the pinyin syllables `text` would produce: one per syllable of the first reading of
+/// each mapped character, so a reading stored as several syllables (`"xi'an"`) counts each.
+/// Characters without a reading are ignored.
+pub fn syllable_count_text(text: &str) -> Result<usize, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(text
+        .chars()
+        .filter_map(|c| map.get(&(c as u32)).and_then(|p_vec| p_vec.first()))
+        .map(|pinyin| {
+            pinyin
+                .split(|c: char| c.is_whitespace() || is_apostrophe(c))
+                .filter(|syllable| !syllable.is_empty())
+                .count()
+        })
+        .sum())
+}
+
+/// Returns the readings `a` and `b` have in common (tone-sensitive), in `a`'s reading order.
+/// Empty if either character has no readings.
+pub fn shared_readings(a: char, b: char) -> Result<Vec<String>, PinyinError> {
+    let map = pinyin_map()?;
+    let (Some(readings_a), Some(readings_b)) = (map.get(&(a as u32)), map.get(&(b as u32))) else {
+        return Ok(Vec::new());
+    };
+    Ok(readings_a
+        .iter()
+        .filter(|reading| readings_b.contains(reading))
+        .cloned()
+        .collect())
+}
+
+/// Counts how much of a Unicode range the loaded map covers, returning
+/// `(mapped_count, total_count)` over the assigned CJK ideographs in `start..=end`.
+/// Codepoints that are not Han ideographs are ignored entirely.
+pub fn block_coverage(start: u32, end: u32) -> Result<(usize, usize), PinyinError> {
+    let mut mapped = 0;
+    let mut total = 0;
+    for (_, readings) in iter_block(start, end)? {
+        total += 1;
+        if readings.is_some() {
+            mapped += 1;
+        }
+    }
+    Ok((mapped, total))
+}
+
+/// Lazily walks the assigned CJK ideographs in `start..=end` in codepoint order, yielding each
+/// with its readings borrowed from the loaded map (`None` if it has none). Nothing is
+/// allocated for the range, so whole blocks can be scanned cheaply; codepoints that are not Han
+/// ideographs are skipped, as in [`block_coverage`].
+pub fn iter_block(
+    start: u32,
+    end: u32,
+) -> Result<impl Iterator<Item = (char, Option<&'static Vec<String>>)>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok((start..=end)
+        .filter(|&cp| han::is_cjk_ideograph(cp))
+        .filter_map(char::from_u32)
+        .map(move |c| (c, map.get(&(c as u32)))))
+}
+
+/// Checks every reading in the loaded map against the valid-syllable inventory (see
+/// [`is_valid_syllable`]) after removing tones and reading `v` as `ü`, and returns the
+/// `(codepoint, reading)` pairs that are not valid syllables, sorted by codepoint.
+pub fn validate_map() -> Result<Vec<(u32, String)>, PinyinError> {
+    let map = pinyin_map()?;
+    let mut invalid: Vec<(u32, String)> = map
+        .iter()
+        .flat_map(|(&codepoint, readings)| {
+            readings
+                .iter()
+                .filter(|reading| {
+                    let syllable = strip_tone(&reading.to_lowercase()).replace('v', "ü");
+                    !is_valid_syllable(&syllable)
+                })
+                .map(move |reading| (codepoint, reading.clone()))
         })
         .collect();
+    invalid.sort_by_key(|&(codepoint, _)| codepoint);
+    Ok(invalid)
+}
 
-    Ok(pinyins.join(separator))
+/// Returns the first reading of `c`, or `c` itself if it has none.
+pub(crate) fn first_pinyin_or_char(map: &HashMap<u32, Vec<String>>, c: char) -> String {
+    map.get(&(c as u32))
+        .and_then(|p_vec| p_vec.first())
+        .map_or(c.to_string(), |p| p.clone())
 }
 
 pub fn diacritic_to_tone_plus_number(pinyins: &[&str]) -> Vec<String> {
@@ -255,6 +806,12 @@ fn replace_diacritic(pinyin: &str) -> String {
     result
 }
 
+/// Converts pinyin with tone numbers to tone marks. A tone number marks the vowel right
+/// before it, as [`diacritic_to_tone_plus_number`] writes it (`"ha3o"` and `"hao3"` are
+/// `"hǎo"` and `"haǒ"`), with `"yu"` standing for `ü` and `v` accepted for it. A number
+/// after a whole syllable that ends in a consonant (`"an1"`) marks the vowel the usual
+/// placement rules pick (`"ān"`). Apostrophes separate syllables (`"xi1'an1"`), and each
+/// syllable is read the same way as on its own; anything else is left as it is.
 pub fn tone_plus_number_to_diacritic(pinyins: &[&str]) -> Vec<String> {
     pinyins
         .iter()
@@ -263,6 +820,94 @@ pub fn tone_plus_number_to_diacritic(pinyins: &[&str]) -> Vec<String> {
 }
 
 fn replace_numbered_pinyin(pinyin: &str) -> String {
+    // An apostrophe separates syllables ("xi1'an1"): convert each one on its own
+    map_syllables(pinyin, replace_numbered_syllable)
+}
+
+/// Rewrites pinyin that mixes tone marks and tone numbers (`"nǐ hao3"`) in one `target`
+/// style, joining the whitespace-separated tokens with `separator`. Each syllable is detected
+/// on its own, so apostrophe-joined words (`"xi1'ān"`) and punctuation around a token
+/// (`"hao3,"`) are handled; anything that is not a valid syllable is kept as it is.
+///
+/// ```
+/// use mandarin_to_pinyin::{ToneStyle, normalize_pinyin_forms};
+///
+/// assert_eq!(
+///     normalize_pinyin_forms("nǐ hao3", ToneStyle::Numbered, " "),
+///     "ni3 hao3"
+/// );
+/// ```
+pub fn normalize_pinyin_forms(s: &str, target: ToneStyle, separator: &str) -> String {
+    let tokens: Vec<String> = s
+        .split_whitespace()
+        .map(|token| {
+            // Punctuation around the token is kept out of syllable detection
+            let is_punctuation = |c: char| !c.is_alphanumeric();
+            let end = token.trim_end_matches(is_punctuation).len();
+            let start = token[..end].len() - token[..end].trim_start_matches(is_punctuation).len();
+            let core = map_syllables(&token[start..end], |syllable| {
+                let toneless = strip_tone(&syllable.to_lowercase()).replace('v', "ü");
+                let is_syllable = is_valid_syllable(&toneless)
+                    || toneless.strip_suffix('r').is_some_and(is_valid_syllable);
+                if is_syllable {
+                    to_tone_style(syllable, target)
+                } else {
+                    syllable.to_string()
+                }
+            });
+            format!("{}{}{}", &token[..start], core, &token[end..])
+        })
+        .collect();
+    tokens.join(separator)
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// Applies `convert` to each apostrophe-separated part of `pinyin`, keeping the apostrophes.
+fn map_syllables(pinyin: &str, convert: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(pinyin.len() * 2);
+    let mut start = 0;
+    for (i, c) in pinyin.char_indices() {
+        if is_apostrophe(c) {
+            result.push_str(&convert(&pinyin[start..i]));
+            result.push(c);
+            start = i + c.len_utf8();
+        }
+    }
+    result.push_str(&convert(&pinyin[start..]));
+    result
+}
+
+fn replace_numbered_syllable(pinyin: &str) -> String {
+    // Erhua ("huar1"): the tone number follows the non-tonal 'r', so read it as if it followed
+    // the syllable before the 'r' ("hua1") and put the 'r' back
+    if let Some(toneless) = pinyin.strip_suffix(['1', '2', '3', '4', '5'])
+        && let Some(stem) = toneless.strip_suffix('r')
+        && is_valid_syllable(&stem.to_lowercase().replace('v', "ü"))
+    {
+        let tone = &pinyin[toneless.len()..];
+        return format!(
+            "{}r",
+            replace_numbered_syllable(&format!("{}{}", stem, tone))
+        );
+    }
+
+    // A number after a final consonant ("an1") has no vowel right before it to mark, so it
+    // marks the syllable by the usual placement rules
+    if let Some(toneless) = pinyin.strip_suffix(['1', '2', '3', '4', '5'])
+        && toneless.ends_with(|c: char| {
+            !matches!(
+                c.to_ascii_lowercase(),
+                'a' | 'e' | 'i' | 'o' | 'u' | 'v' | 'ü' | 'Ü'
+            )
+        })
+        && is_valid_syllable(&toneless.to_lowercase().replace('v', "ü"))
+    {
+        return to_tone_style(pinyin, ToneStyle::Diacritic);
+    }
+
     let mut result = String::with_capacity(pinyin.len());
     let mut chars: Vec<char> = pinyin.chars().collect();
 
@@ -297,14 +942,40 @@ fn replace_numbered_pinyin(pinyin: &str) -> String {
     result
 }
 
+/// Controls how [`save_to_vec_with`] orders the encoded data.
+#[cfg(feature = "prepare-data")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SaveOptions {
+    /// Sorts each character's readings. This changes which reading comes first, and therefore
+    /// the reading every `first`-reading function returns, so leave it off unless the source
+    /// order carries no meaning.
+    pub sort_readings: bool,
+    /// Writes the codepoints in ascending order, so the same map always encodes to the same
+    /// bytes regardless of `HashMap` iteration order.
+    pub sort_keys: bool,
+}
+
 #[cfg(feature = "prepare-data")]
 pub fn save_to_vec(
     pinyin_map: HashMap<u32, Vec<String>>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    save_to_vec_with(pinyin_map, SaveOptions::default())
+}
+
+/// Like [`save_to_vec`], with explicit control over ordering. The output decodes with
+/// [`load_from_bytes`] whatever the options.
+#[cfg(feature = "prepare-data")]
+pub fn save_to_vec_with(
+    pinyin_map: HashMap<u32, Vec<String>>,
+    options: SaveOptions,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     let mappings: HashMap<u32, Vec<String>> = pinyin_map
         .into_iter()
-        .filter_map(|(k, v)| {
+        .filter_map(|(k, mut v)| {
             if k <= 0x10FFFF {
+                if options.sort_readings {
+                    v.sort();
+                }
                 Some((k, v))
             } else {
                 eprintln!("Codepoint {} out of Unicode range", k);
@@ -313,40 +984,190 @@ pub fn save_to_vec(
         })
         .collect();
 
-    let unicode_mapping = UnicodeMapping { mappings };
-
-    let encoded = bincode::encode_to_vec(&unicode_mapping, bincode::config::standard())?;
+    let encoded = if options.sort_keys {
+        // A map encodes the same way whatever its type, so a `BTreeMap` decodes as the
+        // `HashMap` in `UnicodeMapping`.
+        let sorted: std::collections::BTreeMap<u32, Vec<String>> = mappings.into_iter().collect();
+        bincode::encode_to_vec(&sorted, bincode::config::standard())?
+    } else {
+        let unicode_mapping: UnicodeMapping = mappings.into();
+        bincode::encode_to_vec(&unicode_mapping, bincode::config::standard())?
+    };
     Ok(encoded)
 }
 
+/// Computes the version stamp for a source data file: its file name followed by an FNV-1a
+/// hash of its contents, so any edit to the source produces a new version.
+#[cfg(feature = "prepare-data")]
+pub fn data_version(source_name: &str, source: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in source {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{}-{:016x}", source_name, hash)
+}
+
+/// Returns the `(codepoint, reading)` pairs of `pinyin_map` that do not survive a round trip
+/// from tone marks to a tone number and back (`"hǎo"` -> `"hao3"` -> `"hǎo"`), sorted by
+/// codepoint. A reading listed here has its tone mark somewhere the crate's placement rule
+/// (see [`place_tone`]) would not put it (`"gùi"`), or is not written with tone marks at all
+/// (`"hao3"`), so numbered output for it could not be converted back faithfully.
+#[cfg(feature = "prepare-data")]
+pub fn round_trip_failures(pinyin_map: &HashMap<u32, Vec<String>>) -> Vec<(u32, String)> {
+    let mut failures: Vec<(u32, String)> = pinyin_map
+        .iter()
+        .flat_map(|(&codepoint, readings)| {
+            readings
+                .iter()
+                .filter(|reading| {
+                    let numbered = to_tone_style(reading, ToneStyle::Numbered);
+                    to_tone_style(&numbered, ToneStyle::Diacritic) != **reading
+                })
+                .map(move |reading| (codepoint, reading.clone()))
+        })
+        .collect();
+    failures.sort();
+    failures
+}
+
+/// Problems found while parsing a source data file.
+#[cfg(feature = "prepare-data")]
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// Lines that are not a hex codepoint and readings separated by a tab, as
+    /// `(line number, line)`. Line numbers start at 1; blank lines are ignored.
+    pub malformed_lines: Vec<(usize, String)>,
+    /// Codepoints listed on more than one line, in the order their first repeat was seen.
+    /// The last line for a codepoint wins.
+    pub duplicate_codepoints: Vec<u32>,
+}
+
+#[cfg(feature = "prepare-data")]
+impl LoadReport {
+    /// Returns `true` if no problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.malformed_lines.is_empty() && self.duplicate_codepoints.is_empty()
+    }
+}
+
+/// A parsed source file together with the problems found while parsing it.
+#[cfg(feature = "prepare-data")]
+pub type ReportedLoad = Result<(HashMap<u32, Vec<String>>, LoadReport), Box<dyn std::error::Error>>;
+
+/// Parses a source data file of tab-separated hex codepoints and space-separated readings.
+/// Readings keep the order they have on the line.
 #[cfg(feature = "prepare-data")]
 pub fn load_pinyin_map(
     pinyin_data_path: &str,
 ) -> Result<HashMap<u32, Vec<std::string::String>>, Box<dyn std::error::Error>> {
-    use std::collections::HashMap;
+    let file = std::fs::File::open(pinyin_data_path)?;
+    load_pinyin_map_from_reader(std::io::BufReader::new(file))
+}
+
+/// Like [`load_pinyin_map`], but parses source data from any reader (an embedded string, a
+/// network response, a decompressed stream) instead of a file.
+#[cfg(feature = "prepare-data")]
+pub fn load_pinyin_map_from_reader<R: std::io::BufRead>(
+    reader: R,
+) -> Result<HashMap<u32, Vec<String>>, Box<dyn std::error::Error>> {
+    let (pinyin_map, _report) = parse_pinyin_map(reader)?;
+    Ok(pinyin_map)
+}
+
+/// Like [`load_pinyin_map`], but also returns a [`LoadReport`] listing malformed lines and
+/// duplicate codepoints so maintainers can fix the source.
+#[cfg(feature = "prepare-data")]
+pub fn load_pinyin_map_with_report(pinyin_data_path: &str) -> ReportedLoad {
+    use std::fs::File;
+    use std::io;
+
+    let file = File::open(pinyin_data_path)?;
+    parse_pinyin_map(io::BufReader::new(file))
+}
+
+#[cfg(feature = "prepare-data")]
+fn parse_pinyin_map<R: std::io::BufRead>(reader: R) -> ReportedLoad {
+    use std::collections::HashSet;
+
+    let mut pinyin_map: HashMap<u32, Vec<String>> = HashMap::new();
+    let mut report = LoadReport::default();
+    let mut duplicates = HashSet::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split('\t').collect();
+        let codepoint = match parts.as_slice() {
+            [codepoint, _] => u32::from_str_radix(codepoint, 16).ok(),
+            _ => None,
+        };
+        let Some(codepoint) = codepoint else {
+            report.malformed_lines.push((index + 1, line));
+            continue;
+        };
+
+        let p: Vec<String> = parts[1].split_whitespace().map(|s| s.to_string()).collect();
+        if pinyin_map.insert(codepoint, p).is_some() && duplicates.insert(codepoint) {
+            report.duplicate_codepoints.push(codepoint);
+        }
+    }
+
+    Ok((pinyin_map, report))
+}
+
+/// Loads the `kMandarin` readings from a gzip-compressed Unihan readings file
+/// (`Unihan_Readings.txt.gz`), decompressing it on the fly.
+///
+/// Lines are `U+XXXX<TAB>field<TAB>value`; comments, blank lines and fields other than
+/// `kMandarin` are skipped, and lines that do not fit the format are reported as malformed.
+#[cfg(feature = "prepare-data")]
+pub fn load_from_unihan_gz(path: &str) -> ReportedLoad {
     use std::fs::File;
-    use std::io::{self, BufRead};
-    use std::path::Path;
+    use std::io;
+
+    let file = File::open(path)?;
+    parse_unihan_readings(io::BufReader::new(flate2::read::GzDecoder::new(file)))
+}
 
-    let path = Path::new(pinyin_data_path);
-    let file = File::open(&path)?;
-    let reader = io::BufReader::new(file);
+#[cfg(feature = "prepare-data")]
+fn parse_unihan_readings<R: std::io::BufRead>(reader: R) -> ReportedLoad {
+    use std::collections::HashSet;
 
     let mut pinyin_map: HashMap<u32, Vec<String>> = HashMap::new();
+    let mut report = LoadReport::default();
+    let mut duplicates = HashSet::new();
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
         let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() == 2 {
-            if let Ok(codepoint) = u32::from_str_radix(parts[0], 16) {
-                let pinyin = parts[1].to_string();
-                let p: Vec<String> = pinyin.split_whitespace().map(|s| s.to_string()).collect();
-                pinyin_map.insert(codepoint, p);
-            }
+        let entry = match parts.as_slice() {
+            [codepoint, field, value] => codepoint
+                .strip_prefix("U+")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .map(|codepoint| (codepoint, *field, *value)),
+            _ => None,
+        };
+        let Some((codepoint, field, value)) = entry else {
+            report.malformed_lines.push((index + 1, line));
+            continue;
+        };
+        if field != "kMandarin" {
+            continue;
+        }
+
+        let p: Vec<String> = value.split_whitespace().map(|s| s.to_string()).collect();
+        if pinyin_map.insert(codepoint, p).is_some() && duplicates.insert(codepoint) {
+            report.duplicate_codepoints.push(codepoint);
         }
     }
 
-    Ok(pinyin_map)
+    Ok((pinyin_map, report))
 }
 
 #[cfg(test)]
@@ -384,7 +1205,7 @@ mod tests {
         let input6 = vec!["", "hao3", ""];
         let expected6 = vec!["", "haǒ", ""];
         assert_eq!(test_fn(&input6), expected6);
-        
+
         // Test case 7: Pinyin where the letter before the number does not form a valid diacritic
         let input7 = vec!["x1", "z4"];
         let expected7 = vec!["x1", "z4"];
@@ -401,9 +1222,576 @@ mod tests {
         run_diacritic_conversion_tests(&tone_plus_number_to_diacritic);
     }
 
+    #[test]
+    fn test_erhua_tone_placement() {
+        assert_eq!(
+            tone_plus_number_to_diacritic(&["huar1", "wanr2", "er2", "nvr3", "xr1"]),
+            vec!["huār", "wánr", "ér", "nǚr", "xr1"]
+        );
+        // The number marks the syllable the same way as without the 'r'
+        assert_eq!(
+            tone_plus_number_to_diacritic(&["haor3", "hao3", "ha3or"]),
+            vec!["haǒr", "haǒ", "hǎor"]
+        );
+    }
+
+    #[test]
+    fn test_apostrophe_syllable_boundaries() {
+        assert_eq!(
+            tone_plus_number_to_diacritic(&["xi1'an1", "pi2'a3o", "xi1’an1", "nv3'er2"]),
+            vec!["xī'ān", "pí'ǎo", "xī’ān", "nǚ'ér"]
+        );
+        // Each syllable reads its tone number the same way as without an apostrophe
+        assert_eq!(
+            tone_plus_number_to_diacritic(&["hao3", "hao3'ma", "an1", "an1'ma"]),
+            vec!["haǒ", "haǒ'ma", "ān", "ān'ma"]
+        );
+        assert_eq!(
+            diacritic_to_tone_plus_number(&["xī'ān", "pí'ǎo", "nǚ'ér", "hǎo'ma", "'"]),
+            vec!["xi1'a1n", "pi2'a3o", "nv3'e2r", "ha3o'ma", "'"]
+        );
+        // The same format as without an apostrophe, and it converts back
+        assert_eq!(diacritic_to_tone_plus_number(&["hǎo"]), vec!["ha3o"]);
+        assert_eq!(
+            tone_plus_number_to_diacritic(&["xi1'a1n", "ha3o'ma"]),
+            vec!["xī'ān", "hǎo'ma"]
+        );
+    }
+
+    #[test]
+    fn test_lookup_fn() {
+        init_test_map();
+        let lookup = lookup_fn().unwrap();
+        assert_eq!(lookup('你'), Some(&vec!["nǐ".to_string()]));
+        assert_eq!(lookup('A'), None);
+    }
+
+    #[test]
+    fn test_chars_to_pinyin_string() {
+        init_test_map();
+        let chars: Vec<char> = "你好, world".chars().filter(|c| !c.is_ascii()).collect();
+        assert_eq!(chars_to_pinyin_string(&chars, " ").unwrap(), "nǐ hǎo");
+        assert_eq!(chars_to_pinyin_string(&['A', '好'], "-").unwrap(), "A-hǎo");
+        assert_eq!(chars_to_pinyin_string(&[], " ").unwrap(), "");
+    }
+
+    #[cfg(feature = "default-data")]
+    #[test]
+    fn test_subset_map() {
+        let full = load_default().unwrap();
+        let subset = subset_map(&full, "你好, 你好 world");
+        assert_eq!(subset.mappings.len(), 2);
+        assert_eq!(
+            subset.mappings[&('好' as u32)],
+            full.mappings[&('好' as u32)]
+        );
+
+        // The subset survives a round trip through bincode
+        let bytes = bincode::encode_to_vec(&subset, bincode::config::standard()).unwrap();
+        assert_eq!(load_from_bytes(&bytes).unwrap().mappings, subset.mappings);
+
+        assert!(subset_map(&full, "abc").mappings.is_empty());
+    }
+
+    #[cfg(feature = "prepare-data")]
+    #[test]
+    fn test_parse_pinyin_map_report() {
+        let source =
+            "4E00\tyī\n4E01\tdīng zhēng\nnot a line\n\n4E00\tyí\nZZZZ\tbad\n4E01\tdīng\n4E00\tyì\n";
+        let (map, report) = parse_pinyin_map(std::io::Cursor::new(source)).unwrap();
+
+        assert_eq!(map[&0x4E00], vec!["yì"]);
+        assert_eq!(map[&0x4E01], vec!["dīng"]);
+        assert_eq!(report.duplicate_codepoints, vec![0x4E00, 0x4E01]);
+        assert_eq!(
+            report.malformed_lines,
+            vec![(3, "not a line".to_string()), (6, "ZZZZ\tbad".to_string())]
+        );
+        assert!(!report.is_clean());
+    }
+
+    #[cfg(feature = "prepare-data")]
+    #[test]
+    fn test_load_pinyin_map_from_reader() {
+        let source = "4E2D\tzhōng zhòng\n5730\tdì de\n";
+        let map = load_pinyin_map_from_reader(source.as_bytes()).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&0x4E2D], vec!["zhōng", "zhòng"]);
+        assert_eq!(map[&0x5730], vec!["dì", "de"]);
+    }
+
+    #[cfg(feature = "prepare-data")]
+    #[test]
+    fn test_round_trip_failures() {
+        let map = HashMap::from([
+            (0x597D, vec!["hǎo".to_string(), "hào".to_string()]),
+            // A mark on the wrong vowel, and a reading with a tone number
+            (0x8D35, vec!["gùi".to_string()]),
+            (0x4E00, vec!["yī".to_string(), "yi2".to_string()]),
+        ]);
+        assert_eq!(
+            round_trip_failures(&map),
+            vec![(0x4E00, "yi2".to_string()), (0x8D35, "gùi".to_string())]
+        );
+    }
+
+    #[cfg(feature = "prepare-data")]
+    #[test]
+    fn test_load_from_unihan_gz() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let source = "# Unihan_Readings.txt\n\nU+4E2D\tkCantonese\tzung1\nU+4E2D\tkMandarin\tzhōng\nU+5730\tkMandarin\tdì de\nbroken\n";
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(source.as_bytes()).unwrap();
+        let path = std::env::temp_dir().join("mandarin-to-pinyin-unihan-test.txt.gz");
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let (map, report) = load_from_unihan_gz(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&0x4E2D], vec!["zhōng"]);
+        assert_eq!(map[&0x5730], vec!["dì", "de"]);
+        assert_eq!(report.malformed_lines, vec![(6, "broken".to_string())]);
+    }
+
+    #[test]
+    fn test_syllable_count_text() {
+        init_test_map();
+        assert_eq!(syllable_count_text("床前明月光").unwrap(), 5);
+        assert_eq!(syllable_count_text("Hello, 世界!").unwrap(), 2);
+        assert_eq!(syllable_count_text("").unwrap(), 0);
+    }
+
+    #[cfg(feature = "default-data")]
+    #[test]
+    fn test_validate_map() {
+        init_test_map();
+        // The default data has a few dialectal readings outside the standard inventory
+        assert_eq!(
+            validate_map().unwrap(),
+            vec![
+                (0x24B62, "cèi".to_string()),
+                (0x259B7, "wòng".to_string()),
+                (0x28216, "dìn".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unicode_mapping_from_hash_map() {
+        let map = HashMap::from([(0x4E2D, vec!["zhōng".to_string()])]);
+        let mapping: UnicodeMapping = map.clone().into();
+        assert_eq!(mapping.mappings, map);
+        assert_eq!(HashMap::from(mapping), map);
+    }
+
+    #[test]
+    fn test_lookup_utf8() {
+        init_test_map();
+        assert_eq!(
+            lookup_utf8("中".as_bytes()).unwrap(),
+            Some(vec!["zhōng".to_string()])
+        );
+        assert_eq!(lookup_utf8(b"a").unwrap(), None);
+        for bytes in [&b""[..], "中国".as_bytes(), &[0xE4, 0xB8]] {
+            assert_eq!(
+                lookup_utf8(bytes),
+                Err(PinyinError::InvalidUtf8Char(bytes.to_vec()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_chars_vec_with_placeholder() {
+        init_test_map();
+        // U+F900 is a CJK compatibility ideograph without a reading in the default data
+        let keys = ['中', '\u{F900}', 'a'];
+        assert_eq!(
+            lookup_chars_vec_with(&keys, &LookupConfig::default()).unwrap(),
+            lookup_chars_vec(&keys).unwrap()
+        );
+
+        let config = LookupConfig {
+            unmapped_placeholder: UnmappedPlaceholder::CodepointEscape,
+        };
+        assert_eq!(
+            lookup_chars_vec_with(&keys, &config).unwrap(),
+            vec![
+                Some(vec!["zhōng".to_string()]),
+                Some(vec!["U+F900".to_string()]),
+                None
+            ]
+        );
+
+        let config = LookupConfig {
+            unmapped_placeholder: UnmappedPlaceholder::Text("?".to_string()),
+        };
+        assert_eq!(
+            lookup_chars_vec_with(&['\u{F900}'], &config).unwrap(),
+            vec![Some(vec!["?".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_is_mapped() {
+        init_test_map();
+        let map = pinyin_map().unwrap();
+        for c in ['中', '好', 'a', '。', '\u{F900}', '\u{20000}', '\u{2A6DF}'] {
+            assert_eq!(
+                is_mapped(c).unwrap(),
+                map.contains_key(&(c as u32)),
+                "{:?}",
+                c
+            );
+        }
+        assert!(is_mapped('中').unwrap());
+        assert!(!is_mapped('a').unwrap());
+    }
+
+    #[test]
+    fn test_supported_tone_marks() {
+        let diacritics = supported_diacritics();
+        assert_eq!(diacritics.len(), DIACRITIC_TO_LETTER.len());
+        assert!(diacritics.contains(&'ǚ'));
+        assert!(diacritics.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let keys = supported_numbered_keys();
+        assert_eq!(keys.len(), LETTER_TO_DIACRITIC.len());
+        assert!(keys.contains(&"v3"));
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[cfg(feature = "prepare-data")]
+    #[test]
+    fn test_save_to_vec_with_options() {
+        let entries = [
+            (0x4E07, vec!["wàn".to_string(), "mò".to_string()]),
+            (0x4E2D, vec!["zhōng".to_string()]),
+            (0x5730, vec!["de".to_string(), "dì".to_string()]),
+        ];
+        let forward: HashMap<u32, Vec<String>> = entries.iter().cloned().collect();
+        let backward: HashMap<u32, Vec<String>> = entries.iter().rev().cloned().collect();
+
+        let sorted_keys = SaveOptions {
+            sort_keys: true,
+            ..Default::default()
+        };
+        let bytes = save_to_vec_with(forward.clone(), sorted_keys).unwrap();
+        assert_eq!(bytes, save_to_vec_with(backward, sorted_keys).unwrap());
+        assert_eq!(load_from_bytes(&bytes).unwrap().mappings, forward);
+
+        let sorted_readings = SaveOptions {
+            sort_readings: true,
+            ..Default::default()
+        };
+        let mapping =
+            load_from_bytes(&save_to_vec_with(forward, sorted_readings).unwrap()).unwrap();
+        assert_eq!(mapping.mappings[&0x4E07], vec!["mò", "wàn"]);
+        assert_eq!(mapping.mappings[&0x5730], vec!["de", "dì"]);
+    }
+
+    #[test]
+    fn test_estimated_pinyin_len() {
+        init_test_map();
+        for (text, separator) in [("床前明月光", " "), ("装窗双, ok", "--"), ("", " ")] {
+            let actual = to_pinyin_string(text, separator).unwrap().len();
+            assert!(estimated_pinyin_len(text, separator) >= actual, "{}", text);
+        }
+        assert_eq!(estimated_pinyin_len("", " "), 0);
+        assert_eq!(estimated_pinyin_len("你好", "-"), 15);
+    }
+
+    #[test]
+    fn test_export_loaded_map() {
+        init_test_map();
+        let bytes = export_loaded_map().unwrap();
+        assert_eq!(
+            &load_from_bytes(&bytes).unwrap().mappings,
+            pinyin_map().unwrap()
+        );
+        assert_eq!(export_loaded_map().unwrap(), bytes);
+        #[cfg(feature = "default-data")]
+        assert_eq!(bytes, include_bytes!("../bincode/unicode-to-pinyin.bin"));
+    }
+
+    #[test]
+    fn test_to_pinyin_string_truncated() {
+        init_test_map();
+        // "nǐ hǎo shì jiè" is 14 characters
+        assert_eq!(
+            to_pinyin_string_truncated("你好世界", " ", 14).unwrap(),
+            ("nǐ hǎo shì jiè".to_string(), 4)
+        );
+        assert_eq!(
+            to_pinyin_string_truncated("你好世界", " ", 13).unwrap(),
+            ("nǐ hǎo shì…".to_string(), 3)
+        );
+        assert_eq!(
+            to_pinyin_string_truncated("你好世界", " ", 10).unwrap(),
+            ("nǐ hǎo…".to_string(), 2)
+        );
+        assert_eq!(
+            to_pinyin_string_truncated("你好世界", " ", 2).unwrap(),
+            ("…".to_string(), 0)
+        );
+        assert_eq!(
+            to_pinyin_string_truncated("你好", " ", 0).unwrap(),
+            (String::new(), 0)
+        );
+    }
+
+    #[cfg(feature = "default-data")]
+    #[test]
+    fn test_retain_range() {
+        let full = load_default().unwrap().mappings;
+        let uro = retain_range(full.clone(), 0x4E00, 0x9FFF);
+        assert!(uro.contains_key(&0x4E2D));
+        assert!(!uro.contains_key(&0x3400));
+        assert!(!uro.contains_key(&0x20000));
+        assert_eq!(
+            uro.len(),
+            full.keys()
+                .filter(|&&cp| (0x4E00..=0x9FFF).contains(&cp))
+                .count()
+        );
+        assert!(retain_range(full, 0x41, 0x5A).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "default-data")]
+    fn test_load_mapping_uses_given_bytes() {
+        // init_map loads through load_mapping, which must not fall back to the embedded data
+        let mapping = UnicodeMapping::from(HashMap::from([(0x4E2D, vec!["zhòng".to_string()])]));
+        let bytes = bincode::encode_to_vec(&mapping, bincode::config::standard()).unwrap();
+        assert_eq!(
+            load_mapping(Some(&bytes)).unwrap().mappings,
+            mapping.mappings
+        );
+        assert!(load_mapping(None).unwrap().mappings.len() > 1);
+    }
+
+    #[test]
+    fn test_load_from_bytes_errors() {
+        let mapping = UnicodeMapping::from(HashMap::from([(0x4E2D, vec!["zhōng".to_string()])]));
+        let bytes = bincode::encode_to_vec(&mapping, bincode::config::standard()).unwrap();
+        assert_eq!(load_from_bytes(&bytes).unwrap().mappings, mapping.mappings);
+
+        let decode_error = |bytes: &[u8]| {
+            *load_from_bytes(bytes)
+                .unwrap_err()
+                .downcast::<PinyinError>()
+                .unwrap()
+        };
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(matches!(
+            decode_error(truncated),
+            PinyinError::Decode { len, .. } if len == truncated.len()
+        ));
+
+        let mut concatenated = bytes.clone();
+        concatenated.extend_from_slice(&bytes);
+        assert_eq!(
+            decode_error(&concatenated),
+            PinyinError::Decode {
+                len: concatenated.len(),
+                message: format!(
+                    "{} trailing bytes after the mapping ended at byte {}",
+                    bytes.len(),
+                    bytes.len()
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_to_pinyin_column() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_column("你好, 世界!", false).unwrap(),
+            "nǐ\nhǎo\nshì\njiè\n"
+        );
+        assert_eq!(
+            to_pinyin_column("你好, 世界!", true).unwrap(),
+            "nǐ\nhǎo\n,\nshì\njiè\n!\n"
+        );
+        assert_eq!(to_pinyin_column("abc", false).unwrap(), "");
+    }
+
+    #[test]
+    fn test_to_pinyin_debug() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_debug("你a地\u{F900}").unwrap(),
+            vec![
+                "你(U+4F60): nǐ",
+                "地(U+5730): de dì",
+                "\u{F900}(U+F900): (unmapped)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_pinyin_string_decomposed() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_string_decomposed("你好, 绿", " ").unwrap(),
+            "ni\u{030C} ha\u{030C}o ,   lu\u{0308}\u{0300}"
+        );
+    }
+
+    #[test]
+    fn test_to_pinyin_string_ascii_safe() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_string_ascii_safe("女的!", " ", false).unwrap(),
+            "nv3 de5 !"
+        );
+        assert_eq!(
+            to_pinyin_string_ascii_safe("中国人!", "|", true).unwrap(),
+            "zhong1|guo2  |ren2  |!     "
+        );
+    }
+
+    #[test]
+    fn test_normalize_pinyin_forms() {
+        assert_eq!(
+            normalize_pinyin_forms("nǐ hao3", ToneStyle::Diacritic, " "),
+            "nǐ hǎo"
+        );
+        assert_eq!(
+            normalize_pinyin_forms("Nǐ  hao3, lǜ nv3 ma5!", ToneStyle::Numbered, " "),
+            "Ni3 hao3, lv4 nv3 ma5!"
+        );
+        assert_eq!(
+            normalize_pinyin_forms("xi1'ān huar1 zhōng guo2", ToneStyle::Toneless, "-"),
+            "xi'an-huar-zhong-guo"
+        );
+        // Tokens that are not syllables are left alone
+        assert_eq!(
+            normalize_pinyin_forms("iPhone 2024 hao3", ToneStyle::Diacritic, " "),
+            "iPhone 2024 hǎo"
+        );
+        // Sentence-initial capitals keep their case and take the mark
+        assert_eq!(
+            normalize_pinyin_forms("Ai4 An1 Ou3 hao3", ToneStyle::Diacritic, " "),
+            "Ài Ān Ǒu hǎo"
+        );
+        assert_eq!(
+            normalize_pinyin_forms("Ài Ān", ToneStyle::Numbered, " "),
+            "Ai4 An1"
+        );
+    }
+
+    #[test]
+    fn test_to_pinyin_timed() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_timed("你好!").unwrap(),
+            vec![
+                ('你', Some("nǐ".to_string())),
+                ('好', Some("hǎo".to_string())),
+                ('!', None)
+            ]
+        );
+        assert!(to_pinyin_timed("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_to_pinyin_columns() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_columns("床前\r\n明月光。\n\n").unwrap(),
+            vec![
+                vec!["chuáng", "qián"],
+                vec!["míng", "yuè", "guāng", "。"],
+                vec![],
+            ]
+        );
+        assert!(to_pinyin_columns("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shared_readings() {
+        init_test_map();
+        // 地 reads "de dì", 的 reads "de", 弟 reads "dì"
+        assert_eq!(shared_readings('地', '的').unwrap(), vec!["de"]);
+        assert_eq!(shared_readings('地', '弟').unwrap(), vec!["dì"]);
+        assert_eq!(shared_readings('地', '地').unwrap(), vec!["de", "dì"]);
+        // Tone-sensitive: 妈 mā and 马 mǎ share nothing
+        assert!(shared_readings('妈', '马').unwrap().is_empty());
+        assert!(shared_readings('地', 'A').unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_block_coverage() {
+        init_test_map();
+        let (mapped, total) = block_coverage(0x4E00, 0x9FFF).unwrap();
+        assert_eq!(total, 20992);
+        assert!(mapped > 20000 && mapped <= total);
+
+        // Only ideographs count towards the total
+        assert_eq!(block_coverage(0x4DF0, 0x4E01).unwrap(), (2, 2));
+        assert_eq!(block_coverage(0x3040, 0x30FF).unwrap(), (0, 0));
+        assert_eq!(block_coverage(0x9FFF, 0x4E00).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_iter_block() {
+        init_test_map();
+        let entries: Vec<(char, Option<&Vec<String>>)> =
+            iter_block(0x4DFF, 0x4E01).unwrap().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, '一');
+        assert_eq!(entries[0].1.unwrap()[0], "yī");
+        assert_eq!(entries[1].0, '丁');
+
+        assert!(iter_block(0x3040, 0x30FF).unwrap().next().is_none());
+        let unmapped = iter_block(0xF900, 0xF900).unwrap().next();
+        assert_eq!(unmapped, Some(('\u{F900}', None)));
+    }
+
+    #[test]
+    fn test_reading_order_round_trip() {
+        init_test_map();
+        // 万 is listed as "wàn mò" in the source data
+        let expected = vec!["wàn".to_string(), "mò".to_string()];
+        assert_eq!(
+            lookup_chars_vec(&['万']).unwrap(),
+            vec![Some(expected.clone())]
+        );
+
+        let mapping: UnicodeMapping =
+            HashMap::from([(0x4E07, vec!["mò".to_string(), "wàn".to_string()])]).into();
+        let bytes = bincode::encode_to_vec(&mapping, bincode::config::standard()).unwrap();
+        assert_eq!(
+            load_from_bytes(&bytes).unwrap().mappings[&0x4E07],
+            vec!["mò", "wàn"]
+        );
+    }
+
+    #[cfg(feature = "prepare-data")]
+    #[test]
+    fn test_reading_order_survives_load_and_save() {
+        let source = "4E07\tmò wàn\n5730\tdì de\n";
+        let (map, _) = parse_pinyin_map(std::io::Cursor::new(source)).unwrap();
+        let bytes = save_to_vec(map).unwrap();
+        let mapping = load_from_bytes(&bytes).unwrap();
+        assert_eq!(mapping.mappings[&0x4E07], vec!["mò", "wàn"]);
+        assert_eq!(mapping.mappings[&0x5730], vec!["dì", "de"]);
+    }
+
+    #[cfg(feature = "default-data")]
+    #[test]
+    fn test_default_data_version() {
+        assert!(default_data_version().starts_with("Mandarin.dat-"));
+    }
+
     #[test]
     fn test_to_pinyin_string() {
-        init_map(None).unwrap();
+        init_test_map();
         // Test case 1: Normal sentence
         let input1 = "你好世界";
         let expected1 = "nǐ hǎo shì jiè";
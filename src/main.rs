@@ -3,13 +3,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // It ensures that main() always returns a Result.
     #[cfg(feature = "prepare-data")]
     {
-        use mandarin_to_pinyin::{load_pinyin_map, save_to_vec};
+        use mandarin_to_pinyin::{
+            SaveOptions, data_version, load_pinyin_map_with_report, round_trip_failures,
+            save_to_vec_with,
+        };
         use std::fs;
         use std::io::Write;
 
         // No arguments: Generate default bincode file
         let input_pinyin_data_path = "data/Mandarin.dat";
         let output_bin_path = "bincode/unicode-to-pinyin.bin";
+        let output_version_path = "bincode/unicode-to-pinyin.version";
 
         println!(
             "Generating default bincode file: {} from {}",
@@ -19,13 +23,49 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Ensure bincode directory exists
         fs::create_dir_all("bincode")?;
 
-        let pinyin_map_result = load_pinyin_map(input_pinyin_data_path);
+        let pinyin_map_result = load_pinyin_map_with_report(input_pinyin_data_path);
         match pinyin_map_result {
-            Ok(result) => {
-                let encoded_bytes = save_to_vec(result)?;
+            Ok((result, report)) => {
+                for (line_number, line) in &report.malformed_lines {
+                    eprintln!("Warning: malformed line {}: {:?}", line_number, line);
+                }
+                for codepoint in &report.duplicate_codepoints {
+                    eprintln!(
+                        "Warning: codepoint {:04X} is listed more than once; the last line wins",
+                        codepoint
+                    );
+                }
+
+                // Every reading must convert to a tone number and back unchanged
+                let failures = round_trip_failures(&result);
+                if !failures.is_empty() {
+                    for (codepoint, reading) in &failures {
+                        eprintln!(
+                            "Error: reading {:?} of codepoint {:04X} does not round-trip through a tone number",
+                            reading, codepoint
+                        );
+                    }
+                    return Err(format!(
+                        "{} readings failed the round-trip check; {} not written",
+                        failures.len(),
+                        output_bin_path
+                    )
+                    .into());
+                }
+
+                // Sorted codepoints make the output reproducible; reading order is kept
+                let options = SaveOptions {
+                    sort_keys: true,
+                    ..Default::default()
+                };
+                let encoded_bytes = save_to_vec_with(result, options)?;
                 let mut output_file = fs::File::create(output_bin_path)?;
                 output_file.write_all(&encoded_bytes)?;
 
+                let version = data_version("Mandarin.dat", &fs::read(input_pinyin_data_path)?);
+                fs::write(output_version_path, format!("{}\n", version))?;
+                println!("Data version: {}", version);
+
                 println!("Default bincode file generated successfully.");
             }
             Err(e) => println!("Error: {}", e), // Output: Error: No valid value found
@@ -38,20 +78,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // It provides a runtime error message.
     #[cfg(not(feature = "prepare-data"))]
     {
-        use mandarin_to_pinyin::{init_map, to_pinyin_string};
+        use mandarin_to_pinyin::{init_map, to_pinyin_column, to_pinyin_string};
         use std::env;
 
         let args: Vec<String> = env::args().collect();
+        let column = args.get(1).is_some_and(|arg| arg == "--column");
+        let text_index = if column { 2 } else { 1 };
 
-        if args.len() < 2 {
-            eprintln!("Usage: {} <chinese_string>", args[0]);
+        let Some(chinese_string) = args.get(text_index) else {
+            eprintln!("Usage: {} [--column] <chinese_string>", args[0]);
             return Ok(());
-        }
-
-        let chinese_string = &args[1];
+        };
 
         init_map(None)?;
 
+        if column {
+            // One reading per line, for piping into sort, uniq, etc.
+            match to_pinyin_column(chinese_string, false) {
+                Ok(pinyin) => print!("{}", pinyin),
+                Err(e) => eprintln!("Error converting to pinyin: {}", e),
+            }
+            return Ok(());
+        }
+
         match to_pinyin_string(chinese_string, " ") {
             Ok(pinyin) => println!("{}", pinyin),
             Err(e) => eprintln!("Error converting to pinyin: {}", e),
@@ -0,0 +1,120 @@
+use crate::{PinyinError, first_pinyin_or_char, pinyin_map};
+
+/// Converts the text of an HTML or XML fragment to pinyin, copying tags through verbatim.
+///
+/// Text between tags is converted like [`to_pinyin_string`](crate::to_pinyin_string), each
+/// span on its own, so no separator is inserted next to a tag: `"<b>你好</b>"` becomes
+/// `"<b>nǐ hǎo</b>"`.
+///
+/// A tag is anything from `<` to the next `>`; a `<` with no `>` before the next `<` is plain
+/// text. This is a heuristic, not an HTML parser: comments and attribute values containing `>`
+/// end the tag early, the contents of `<script>` and `<style>` are converted like any other
+/// text, and markdown syntax is not recognized.
+pub fn to_pinyin_preserving_tags(text: &str, separator: &str) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut result = String::with_capacity(text.len() * 2);
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let (span, is_tag) = match next_tag(rest) {
+            Some((0, end)) => (&rest[..end], true),
+            Some((start, _)) => (&rest[..start], false),
+            None => (rest, false),
+        };
+        if is_tag {
+            result.push_str(span);
+        } else {
+            let pinyins: Vec<String> = span.chars().map(|c| first_pinyin_or_char(map, c)).collect();
+            result.push_str(&pinyins.join(separator));
+        }
+        rest = &rest[span.len()..];
+    }
+
+    Ok(result)
+}
+
+/// Produces an SSML fragment for TTS engines that take pinyin phoneme hints: every character
+/// with a reading is wrapped as `<phoneme alphabet="x-pinyin" ph="nǐ">你</phoneme>` using its
+/// first reading, and all other text is XML-escaped. Wrap the result in `<speak>` to make a
+/// complete document.
+pub fn to_ssml(text: &str) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut result = String::with_capacity(text.len() * 8);
+    for c in text.chars() {
+        match map.get(&(c as u32)).and_then(|p_vec| p_vec.first()) {
+            Some(pinyin) => {
+                result.push_str("<phoneme alphabet=\"x-pinyin\" ph=\"");
+                push_escaped(&mut result, pinyin);
+                result.push_str("\">");
+                result.push(c);
+                result.push_str("</phoneme>");
+            }
+            None => push_escaped(&mut result, c.encode_utf8(&mut [0; 4])),
+        }
+    }
+    Ok(result)
+}
+
+fn push_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Finds the first tag in `text`, returning the byte range `start..end` of `<...>`.
+fn next_tag(text: &str) -> Option<(usize, usize)> {
+    let mut search = 0;
+    while let Some(offset) = text[search..].find('<') {
+        let start = search + offset;
+        let after = &text[start + 1..];
+        match after.find(['<', '>']) {
+            Some(i) if after[i..].starts_with('>') => return Some((start, start + 1 + i + 1)),
+            Some(i) => search = start + 1 + i,
+            None => return None,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_to_pinyin_preserving_tags() {
+        init_test_map();
+        assert_eq!(
+            to_pinyin_preserving_tags("<b>你好</b>", " ").unwrap(),
+            "<b>nǐ hǎo</b>"
+        );
+        assert_eq!(
+            to_pinyin_preserving_tags("<p class=\"中\">世界</p>", "-").unwrap(),
+            "<p class=\"中\">shì-jiè</p>"
+        );
+        // A stray `<` is ordinary text
+        assert_eq!(
+            to_pinyin_preserving_tags("1<2 <i>中</i>", " ").unwrap(),
+            "1 < 2  <i>zhōng</i>"
+        );
+        assert_eq!(to_pinyin_preserving_tags("中<", " ").unwrap(), "zhōng <");
+        assert_eq!(to_pinyin_preserving_tags("", " ").unwrap(), "");
+    }
+
+    #[test]
+    fn test_to_ssml() {
+        init_test_map();
+        assert_eq!(
+            to_ssml("你 & <b>").unwrap(),
+            "<phoneme alphabet=\"x-pinyin\" ph=\"nǐ\">你</phoneme> &amp; &lt;b&gt;"
+        );
+        assert_eq!(to_ssml("").unwrap(), "");
+    }
+}
@@ -0,0 +1,62 @@
+/// Returns the reading of `c` when it is used as a numeral, or `None` if `c` is not a
+/// Chinese numeral character.
+///
+/// This covers the digits (including 〇 and the counting form 两), the compact multiples of
+/// ten 廿 (20), 卅 (30) and 卌 (40), the powers of ten up to 兆, and the financial forms
+/// (壹, 贰, ...). The reading is the one the character has in numbers, which is not always its
+/// first dictionary reading (万 reads `wàn`, not `mò`).
+pub fn number_reading(c: char) -> Option<&'static str> {
+    Some(match c {
+        '〇' | '零' => "líng",
+        '一' | '壹' => "yī",
+        '幺' => "yāo",
+        '二' | '贰' | '貳' => "èr",
+        '两' | '兩' => "liǎng",
+        '三' | '叁' | '參' => "sān",
+        '四' | '肆' => "sì",
+        '五' | '伍' => "wǔ",
+        '六' | '陆' | '陸' => "liù",
+        '七' | '柒' => "qī",
+        '八' | '捌' => "bā",
+        '九' | '玖' => "jiǔ",
+        '十' | '拾' => "shí",
+        '廿' => "niàn",
+        '卅' => "sà",
+        '卌' => "xì",
+        '百' | '佰' => "bǎi",
+        '千' | '仟' => "qiān",
+        '万' | '萬' => "wàn",
+        '亿' | '億' => "yì",
+        '兆' => "zhào",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_reading() {
+        assert_eq!(number_reading('〇'), Some("líng"));
+        assert_eq!(number_reading('两'), Some("liǎng"));
+        assert_eq!(number_reading('廿'), Some("niàn"));
+        assert_eq!(number_reading('卅'), Some("sà"));
+        assert_eq!(number_reading('万'), Some("wàn"));
+        assert_eq!(number_reading('贰'), Some("èr"));
+        assert_eq!(number_reading('好'), None);
+    }
+
+    #[cfg(feature = "default-data")]
+    #[test]
+    fn test_default_data_has_numerals() {
+        use crate::{init_test_map, to_pinyin_string};
+
+        init_test_map();
+        assert_eq!(
+            to_pinyin_string("二〇二四年", " ").unwrap(),
+            "èr líng èr sì nián"
+        );
+        assert_eq!(to_pinyin_string("廿卅", " ").unwrap(), "niàn sà");
+    }
+}
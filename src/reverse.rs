@@ -0,0 +1,145 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::tone::{ToneStyle, canonical_key, to_tone_style};
+use crate::{PinyinError, pinyin_map};
+
+static PINYIN_TO_CHARS: OnceLock<HashMap<String, Vec<char>>> = OnceLock::new();
+static READING_FREQUENCIES: OnceLock<HashMap<(char, String), u32>> = OnceLock::new();
+
+/// Initializes the global reading frequency table used by [`lookup_by_pinyin_ranked`], keyed
+/// by character and reading (tone marks or a tone number, e.g. `('好', "hao3")`).
+///
+/// Returns an error if the table has already been initialized.
+pub fn init_reading_frequencies(
+    frequencies: HashMap<(char, String), u32>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let frequencies = frequencies
+        .into_iter()
+        .map(|((c, reading), count)| ((c, reading_key(&reading)), count))
+        .collect();
+    READING_FREQUENCIES
+        .set(frequencies)
+        .map_err(|_| "failed to set reading frequencies in OnceLock")?;
+
+    Ok(())
+}
+
+/// Returns the characters that have `syllable` among their readings, in source (codepoint)
+/// order. `syllable` may use tone marks or a tone number (`"hǎo"` or `"hao3"`); a syllable
+/// without either only matches neutral-tone readings.
+pub fn lookup_by_pinyin(syllable: &str) -> Result<Vec<char>, PinyinError> {
+    Ok(reverse_index()?
+        .get(&reading_key(syllable))
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// Like [`lookup_by_pinyin`], but most frequent first according to the table loaded with
+/// [`init_reading_frequencies`]. Characters without a frequency rank last, and ties keep
+/// source order, so without a table this is the same as `lookup_by_pinyin`.
+pub fn lookup_by_pinyin_ranked(syllable: &str) -> Result<Vec<char>, PinyinError> {
+    let mut chars = lookup_by_pinyin(syllable)?;
+    if let Some(frequencies) = READING_FREQUENCIES.get() {
+        let key = reading_key(syllable);
+        chars.sort_by_key(|&c| {
+            std::cmp::Reverse(frequencies.get(&(c, key.clone())).copied().unwrap_or(0))
+        });
+    }
+    Ok(chars)
+}
+
+/// Collapses the readings of `c` to one deterministic key for lightweight indexes: the most
+/// frequent reading according to the table loaded with [`init_reading_frequencies`] (the
+/// earliest on ties), or the first reading without one, passed through
+/// [`canonical_key`](crate::canonical_key). Returns `None` if `c` has no reading.
+pub fn canonical_reading(c: char) -> Result<Option<String>, PinyinError> {
+    let Some(readings) = pinyin_map()?.get(&(c as u32)) else {
+        return Ok(None);
+    };
+    let frequency = |reading: &String| {
+        READING_FREQUENCIES
+            .get()
+            .and_then(|frequencies| frequencies.get(&(c, reading_key(reading))))
+            .copied()
+            .unwrap_or(0)
+    };
+    let chosen = readings
+        .iter()
+        .enumerate()
+        .max_by_key(|&(index, reading)| (frequency(reading), std::cmp::Reverse(index)))
+        .map(|(_, reading)| canonical_key(reading));
+    Ok(chosen)
+}
+
+pub(crate) fn reverse_index() -> Result<&'static HashMap<String, Vec<char>>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(PINYIN_TO_CHARS.get_or_init(|| {
+        let mut codepoints: Vec<u32> = map.keys().copied().collect();
+        codepoints.sort_unstable();
+
+        let mut index: HashMap<String, Vec<char>> = HashMap::new();
+        for codepoint in codepoints {
+            let Some(c) = char::from_u32(codepoint) else {
+                continue;
+            };
+            for reading in &map[&codepoint] {
+                let chars = index.entry(reading_key(reading)).or_default();
+                if !chars.contains(&c) {
+                    chars.push(c);
+                }
+            }
+        }
+        index
+    }))
+}
+
+/// Normalizes a reading to lowercase tone marks, so `"Hao3"` and `"hǎo"` compare equal.
+fn reading_key(reading: &str) -> String {
+    to_tone_style(&reading.to_lowercase(), ToneStyle::Diacritic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    /// Every test loads the same table, so whichever runs first wins harmlessly.
+    fn init_frequencies() {
+        let _ = init_reading_frequencies(HashMap::from([
+            (('好', "hao3".to_string()), 1000),
+            (('郝', "hǎo".to_string()), 10),
+            (('地', "dì".to_string()), 50),
+        ]));
+    }
+
+    #[test]
+    fn test_lookup_by_pinyin() {
+        init_test_map();
+        let chars = lookup_by_pinyin("hǎo").unwrap();
+        assert!(chars.contains(&'好'));
+        assert!(chars.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(lookup_by_pinyin("hao3").unwrap(), chars);
+        assert!(lookup_by_pinyin("de").unwrap().contains(&'地'));
+        assert!(lookup_by_pinyin("xyz").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lookup_by_pinyin_ranked() {
+        init_test_map();
+        init_frequencies();
+        let ranked = lookup_by_pinyin_ranked("hǎo").unwrap();
+        assert_eq!(&ranked[..2], ['好', '郝']);
+        assert_eq!(ranked.len(), lookup_by_pinyin("hǎo").unwrap().len());
+    }
+
+    #[test]
+    fn test_canonical_reading() {
+        init_test_map();
+        init_frequencies();
+        // 地 lists de first, but the frequency table prefers dì
+        assert_eq!(canonical_reading('地').unwrap().as_deref(), Some("di"));
+        assert_eq!(canonical_reading('女').unwrap().as_deref(), Some("nü"));
+        assert_eq!(canonical_reading('居').unwrap().as_deref(), Some("jü"));
+        assert_eq!(canonical_reading('a').unwrap(), None);
+    }
+}
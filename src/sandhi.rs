@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use crate::tone::{normalize_u, place_tone, strip_tone, tone_number};
+use crate::words::{WordMap, segment, word_map};
+use crate::{PinyinError, pinyin_map};
+
+/// A character of the input, with its tones before and after sandhi if it has a reading.
+pub(crate) struct SandhiUnit {
+    pub ch: char,
+    pub syllable: Option<SandhiSyllable>,
+}
+
+pub(crate) struct SandhiSyllable {
+    pub toneless: String,
+    /// The dictionary (citation) tone.
+    pub citation: u8,
+    /// The tone actually pronounced after sandhi.
+    pub surface: u8,
+    /// Index of the word this syllable belongs to.
+    pub word: usize,
+    /// Whether this is the last syllable of a multi-character dictionary word.
+    pub ends_word: bool,
+}
+
+impl SandhiSyllable {
+    pub(crate) fn surface_form(&self) -> String {
+        place_tone(&self.toneless, self.surface)
+    }
+
+    pub(crate) fn citation_form(&self) -> String {
+        place_tone(&self.toneless, self.citation)
+    }
+}
+
+/// Converts `text` to pinyin with tone sandhi applied, rendering diacritics.
+///
+/// The rules applied are:
+/// * 不 (bù) becomes bú before a fourth tone;
+/// * 一 (yī) becomes yí before a fourth tone and yì before a first, second or third tone;
+/// * a third tone followed by another third tone becomes a second tone.
+///
+/// If a word dictionary has been loaded with [`init_word_map`](crate::init_word_map), text is
+/// segmented into words: dictionary readings override per-character readings, third-tone
+/// sandhi is applied left to right inside each word and then right to left across word
+/// boundaries (so 小老虎 reads xiǎo láo hǔ). Without a dictionary every character is its own
+/// word, which gets common two-syllable cases (你好 -> ní hǎo) right but can misplace the
+/// change in longer runs of third tones. 一 keeps its first tone at the end of a dictionary
+/// word (第一天 -> dì yī tiān), so ordinals are only recognized when the dictionary has them;
+/// 一 and 不 between reduplicated verbs (看一看) are not neutralized.
+pub fn to_pinyin_string_sandhi(text: &str, separator: &str) -> Result<String, PinyinError> {
+    let units = sandhi_units(pinyin_map()?, word_map(), text);
+    let pinyins: Vec<String> = units
+        .iter()
+        .map(|unit| match &unit.syllable {
+            Some(syllable) => syllable.surface_form(),
+            None => unit.ch.to_string(),
+        })
+        .collect();
+    Ok(pinyins.join(separator))
+}
+
+/// Like [`to_pinyin_string_sandhi`] with a space separator, but shows each tone change for
+/// learners: a syllable whose pronounced tone differs from its dictionary tone is written as
+/// the dictionary form followed by the pronounced form in parentheses, e.g. 你好 ->
+/// `"nǐ(ní) hǎo"` and 不是 -> `"bù(bú) shì"`.
+pub fn to_pinyin_sandhi_annotated(text: &str) -> Result<String, PinyinError> {
+    let units = sandhi_units(pinyin_map()?, word_map(), text);
+    let pinyins: Vec<String> = units
+        .iter()
+        .map(|unit| match &unit.syllable {
+            Some(syllable) if syllable.surface != syllable.citation => {
+                format!("{}({})", syllable.citation_form(), syllable.surface_form())
+            }
+            Some(syllable) => syllable.surface_form(),
+            None => unit.ch.to_string(),
+        })
+        .collect();
+    Ok(pinyins.join(" "))
+}
+
+/// Rewrites the neutral-tone particle 啊 (`a`, or `a5`/`a0` in numbered style) according to the
+/// final of the syllable before it:
+/// * after `a`, `e`, `i`, `o`, `ü` or `ê` it becomes `ya` (呀);
+/// * after `u`, `ao` or `ou` it becomes `wa` (哇), so 好啊 reads hǎo wa;
+/// * after `-n` it becomes `na` (哪), so 天啊 reads tiān na; after `-ng`, `nga`;
+/// * after `zhi`, `chi`, `shi`, `ri`, `er` or an erhua `-r` it becomes `ra`;
+/// * after `zi`, `ci` or `si` it becomes `za`.
+///
+/// An `a` at the start, or after a syllable without a vowel (`m`, `hm`), is left alone, as are
+/// toned readings such as `ā`.
+pub fn apply_a_sandhi(syllables: &mut [String]) {
+    for i in 1..syllables.len() {
+        let suffix = match syllables[i].as_str() {
+            "a" => "",
+            "a5" => "5",
+            "a0" => "0",
+            _ => continue,
+        };
+        let previous = normalize_u(&strip_tone(&syllables[i - 1].to_lowercase()));
+        let Some(particle) = assimilated_a(&previous) else {
+            continue;
+        };
+        syllables[i] = format!("{}{}", particle, suffix);
+    }
+}
+
+fn assimilated_a(previous: &str) -> Option<&'static str> {
+    Some(match previous {
+        "zhi" | "chi" | "shi" | "ri" | "er" => "ra",
+        "zi" | "ci" | "si" => "za",
+        p if p.ends_with("ng") => "nga",
+        p if p.ends_with('n') => "na",
+        p if p.ends_with('r') => "ra",
+        p if p.ends_with("ao") || p.ends_with('u') => "wa",
+        p if p.ends_with(['a', 'e', 'i', 'o', 'ü', 'ê']) => "ya",
+        _ => return None,
+    })
+}
+
+pub(crate) fn sandhi_units(
+    map: &HashMap<u32, Vec<String>>,
+    words: Option<&WordMap>,
+    text: &str,
+) -> Vec<SandhiUnit> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut units = Vec::with_capacity(chars.len());
+
+    for (word, segment) in segment(words, &chars).iter().enumerate() {
+        for offset in 0..segment.len {
+            let ch = chars[segment.start + offset];
+            let reading = match segment.readings {
+                Some(readings) => Some(&readings[offset]),
+                None => map.get(&(ch as u32)).and_then(|p_vec| p_vec.first()),
+            };
+            let syllable = reading.map(|reading| {
+                let tone = tone_number(reading);
+                SandhiSyllable {
+                    toneless: strip_tone(reading),
+                    citation: tone,
+                    surface: tone,
+                    word,
+                    ends_word: segment.len > 1 && offset == segment.len - 1,
+                }
+            });
+            units.push(SandhiUnit { ch, syllable });
+        }
+    }
+
+    apply_bu_yi_sandhi(&mut units);
+    apply_third_tone_sandhi(&mut units);
+    units
+}
+
+fn next_citation(units: &[SandhiUnit], i: usize) -> Option<u8> {
+    units
+        .get(i + 1)
+        .and_then(|unit| unit.syllable.as_ref())
+        .map(|syllable| syllable.citation)
+}
+
+fn apply_bu_yi_sandhi(units: &mut [SandhiUnit]) {
+    for i in 0..units.len() {
+        let next = next_citation(units, i);
+        let ch = units[i].ch;
+        let Some(syllable) = units[i].syllable.as_mut() else {
+            continue;
+        };
+        if syllable.ends_word {
+            continue;
+        }
+        match (ch, syllable.citation, next) {
+            ('不', 4, Some(4)) => syllable.surface = 2,
+            ('一', 1, Some(4)) => syllable.surface = 2,
+            ('一', 1, Some(1..=3)) => syllable.surface = 4,
+            _ => {}
+        }
+    }
+}
+
+fn apply_third_tone_sandhi(units: &mut [SandhiUnit]) {
+    // Inside a word: every third tone followed by a third tone changes.
+    for i in 0..units.len().saturating_sub(1) {
+        let (left, right) = units.split_at_mut(i + 1);
+        if let (Some(a), Some(b)) = (left[i].syllable.as_mut(), right[0].syllable.as_ref())
+            && a.word == b.word
+            && a.citation == 3
+            && b.citation == 3
+        {
+            a.surface = 2;
+        }
+    }
+
+    // Across words, right to left on the tones already changed, so a changed syllable no
+    // longer triggers sandhi on the word before it.
+    for i in (0..units.len().saturating_sub(1)).rev() {
+        let (left, right) = units.split_at_mut(i + 1);
+        if let (Some(a), Some(b)) = (left[i].syllable.as_mut(), right[0].syllable.as_ref())
+            && a.word != b.word
+            && a.surface == 3
+            && b.surface == 3
+        {
+            a.surface = 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    fn render(units: &[SandhiUnit]) -> String {
+        units
+            .iter()
+            .map(|unit| match &unit.syllable {
+                Some(syllable) => syllable.surface_form(),
+                None => unit.ch.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_to_pinyin_string_sandhi() {
+        init_test_map();
+        assert_eq!(to_pinyin_string_sandhi("你好", " ").unwrap(), "ní hǎo");
+        assert_eq!(to_pinyin_string_sandhi("不是", " ").unwrap(), "bú shì");
+        assert_eq!(to_pinyin_string_sandhi("不好", " ").unwrap(), "bù hǎo");
+        assert_eq!(to_pinyin_string_sandhi("一样", " ").unwrap(), "yí yàng");
+        assert_eq!(to_pinyin_string_sandhi("一天", " ").unwrap(), "yì tiān");
+        assert_eq!(to_pinyin_string_sandhi("一", " ").unwrap(), "yī");
+        assert_eq!(
+            to_pinyin_string_sandhi("我很好!", "-").unwrap(),
+            "wǒ-hén-hǎo-!"
+        );
+        // Non-Chinese characters break adjacency
+        assert_eq!(to_pinyin_string_sandhi("你,好", " ").unwrap(), "nǐ , hǎo");
+    }
+
+    #[test]
+    fn test_to_pinyin_sandhi_annotated() {
+        init_test_map();
+        assert_eq!(to_pinyin_sandhi_annotated("你好").unwrap(), "nǐ(ní) hǎo");
+        assert_eq!(to_pinyin_sandhi_annotated("不是").unwrap(), "bù(bú) shì");
+        assert_eq!(
+            to_pinyin_sandhi_annotated("一天!").unwrap(),
+            "yī(yì) tiān !"
+        );
+        assert_eq!(to_pinyin_sandhi_annotated("中国").unwrap(), "zhōng guó");
+    }
+
+    #[test]
+    fn test_apply_a_sandhi() {
+        init_test_map();
+        let convert = |text: &str| -> Vec<String> {
+            let mut syllables: Vec<String> = crate::to_pinyin_string(text, " ")
+                .unwrap()
+                .split(' ')
+                .map(String::from)
+                .collect();
+            apply_a_sandhi(&mut syllables);
+            syllables
+        };
+        assert_eq!(convert("好啊"), vec!["hǎo", "wa"]);
+        assert_eq!(convert("天啊"), vec!["tiān", "na"]);
+        assert_eq!(convert("去啊"), vec!["qù", "ya"]);
+        assert_eq!(convert("是啊"), vec!["shì", "ra"]);
+        assert_eq!(convert("唱啊"), vec!["chàng", "nga"]);
+        assert_eq!(convert("啊"), vec!["a"]);
+
+        let mut numbered = vec!["hao3".to_string(), "a5".to_string()];
+        apply_a_sandhi(&mut numbered);
+        assert_eq!(numbered, vec!["hao3", "wa5"]);
+    }
+
+    #[test]
+    fn test_sandhi_with_word_dictionary() {
+        init_test_map();
+        let map = pinyin_map().unwrap();
+        let words = WordMap::new(HashMap::from([
+            (
+                "老虎".to_string(),
+                vec!["lǎo".to_string(), "hǔ".to_string()],
+            ),
+            (
+                "展览馆".to_string(),
+                vec!["zhǎn".to_string(), "lǎn".to_string(), "guǎn".to_string()],
+            ),
+            ("第一".to_string(), vec!["dì".to_string(), "yī".to_string()]),
+        ]))
+        .unwrap();
+
+        assert_eq!(render(&sandhi_units(map, None, "小老虎")), "xiǎo láo hǔ");
+        assert_eq!(
+            render(&sandhi_units(map, Some(&words), "小老虎")),
+            "xiǎo láo hǔ"
+        );
+        assert_eq!(
+            render(&sandhi_units(map, Some(&words), "展览馆")),
+            "zhán lán guǎn"
+        );
+        assert_eq!(render(&sandhi_units(map, None, "展览馆")), "zhǎn lán guǎn");
+
+        // 一 at the end of a word keeps its citation tone
+        assert_eq!(render(&sandhi_units(map, None, "第一天")), "dì yì tiān");
+        assert_eq!(
+            render(&sandhi_units(map, Some(&words), "第一天")),
+            "dì yī tiān"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{PinyinError, pinyin_map};
+
+/// The global map with every reading interned as an `Arc<str>`, so all characters sharing a
+/// reading point at one allocation. Built from the global map on first use.
+static SHARED_UNICODE_TO_PINYIN: OnceLock<HashMap<u32, Vec<Arc<str>>>> = OnceLock::new();
+
+fn shared_map() -> Result<&'static HashMap<u32, Vec<Arc<str>>>, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(SHARED_UNICODE_TO_PINYIN.get_or_init(|| {
+        let mut interned: HashMap<&str, Arc<str>> = HashMap::new();
+        map.iter()
+            .map(|(&codepoint, readings)| {
+                let readings = readings
+                    .iter()
+                    .map(|reading| {
+                        interned
+                            .entry(reading.as_str())
+                            .or_insert_with(|| Arc::from(reading.as_str()))
+                            .clone()
+                    })
+                    .collect();
+                (codepoint, readings)
+            })
+            .collect()
+    }))
+}
+
+/// Looks up the readings of `c` as shared strings, in the map's order. Cloning a returned
+/// reading only bumps a reference count, which suits callers that keep millions of converted
+/// tokens in memory. The interned table is built from the global map on the first call.
+pub fn lookup_shared(c: char) -> Result<Option<&'static [Arc<str>]>, PinyinError> {
+    Ok(shared_map()?.get(&(c as u32)).map(Vec::as_slice))
+}
+
+/// Pairs each character of `text` with its first reading as a shared string, or `None` for
+/// characters without one. See [`lookup_shared`].
+pub fn to_pinyin_shared(text: &str) -> Result<Vec<Option<Arc<str>>>, PinyinError> {
+    let map = shared_map()?;
+    Ok(text
+        .chars()
+        .map(|c| {
+            map.get(&(c as u32))
+                .and_then(|p_vec| p_vec.first())
+                .cloned()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_to_pinyin_shared() {
+        init_test_map();
+        let readings = to_pinyin_shared("地a的").unwrap();
+        assert_eq!(readings.len(), 3);
+        assert_eq!(readings[0].as_deref(), Some("de"));
+        assert_eq!(readings[1], None);
+        assert_eq!(readings[2].as_deref(), Some("de"));
+        // Different characters with the same reading share one allocation
+        assert!(Arc::ptr_eq(
+            readings[0].as_ref().unwrap(),
+            readings[2].as_ref().unwrap()
+        ));
+
+        let de: Vec<&str> = lookup_shared('地')
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(|r| &**r)
+            .collect();
+        assert_eq!(de, vec!["de", "dì"]);
+        assert!(lookup_shared('a').unwrap().is_none());
+    }
+}
@@ -0,0 +1,213 @@
+use std::io::{BufRead, Write};
+
+use crate::{first_pinyin_or_char, pinyin_map};
+
+/// Converts the caption text of an SRT subtitle stream to pinyin, copying everything else
+/// verbatim.
+///
+/// A line is left untouched if it is blank, is a cue index (only ASCII digits), or is a timing
+/// line (contains `-->`). Every other line is converted like
+/// [`to_pinyin_string`](crate::to_pinyin_string) with `separator`. Line endings, including
+/// `\r\n`, are preserved.
+pub fn convert_srt<R: BufRead, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    separator: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let map = pinyin_map()?;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+
+        if is_srt_structure_line(content) {
+            writer.write_all(content.as_bytes())?;
+        } else {
+            let pinyins: Vec<String> = content
+                .chars()
+                .map(|c| first_pinyin_or_char(map, c))
+                .collect();
+            writer.write_all(pinyins.join(separator).as_bytes())?;
+        }
+        writer.write_all(ending.as_bytes())?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Converts a text stream to pinyin on a tokio runtime, like
+/// [`to_pinyin_string`](crate::to_pinyin_string) with `separator` but incrementally: output is
+/// written as input arrives, and a character split across reads is held back until it is
+/// complete. Fails if the input is not valid UTF-8.
+#[cfg(feature = "tokio")]
+pub async fn convert_async_stream<R, W>(
+    mut reader: R,
+    mut writer: W,
+    separator: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let map = pinyin_map()?;
+    let mut pending: Vec<u8> = Vec::new();
+    let mut first = true;
+
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            break;
+        }
+        pending.extend_from_slice(chunk);
+        let consumed = chunk.len();
+        reader.consume(consumed);
+
+        let valid_len = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            // An incomplete character at the end is completed by the next read
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(e.into()),
+        };
+        let text = std::str::from_utf8(&pending[..valid_len])?;
+
+        let mut output = String::with_capacity(text.len() * 2);
+        for c in text.chars() {
+            if !first {
+                output.push_str(separator);
+            }
+            output.push_str(&first_pinyin_or_char(map, c));
+            first = false;
+        }
+        writer.write_all(output.as_bytes()).await?;
+        pending.drain(..valid_len);
+    }
+
+    if !pending.is_empty() {
+        return Err("stream ended in the middle of a UTF-8 character".into());
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Writes one JSON object per character of `text` that has readings, as newline-delimited
+/// JSON (JSON Lines): the character and all its readings in the map's order, e.g.
+/// `{"c":"你","py":["nǐ"]}`. Objects are written as they are produced, so no document is built
+/// in memory. Other characters are skipped.
+pub fn to_pinyin_jsonl<W: Write>(
+    text: &str,
+    mut writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let map = pinyin_map()?;
+    let mut line = String::new();
+
+    for c in text.chars() {
+        let Some(readings) = map.get(&(c as u32)) else {
+            continue;
+        };
+        line.clear();
+        line.push_str("{\"c\":");
+        push_json_string(&mut line, c.encode_utf8(&mut [0; 4]));
+        line.push_str(",\"py\":[");
+        for (i, reading) in readings.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            push_json_string(&mut line, reading);
+        }
+        line.push_str("]}\n");
+        writer.write_all(line.as_bytes())?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Appends `text` as a quoted JSON string.
+fn push_json_string(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn is_srt_structure_line(line: &str) -> bool {
+    let line = line.trim_start_matches('\u{feff}').trim();
+    line.is_empty() || line.chars().all(|c| c.is_ascii_digit()) || line.contains("-->")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_convert_srt() {
+        init_test_map();
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\n你好\r\n\r\n2\n00:00:03,000 --> 00:00:04,000\n世界 2\n中国";
+        let expected = "1\r\n00:00:01,000 --> 00:00:02,500\r\nnǐ hǎo\r\n\r\n2\n00:00:03,000 --> 00:00:04,000\nshì jiè   2\nzhōng guó";
+
+        let mut output = Vec::new();
+        convert_srt(srt.as_bytes(), &mut output, " ").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_to_pinyin_jsonl() {
+        init_test_map();
+        let mut output = Vec::new();
+        to_pinyin_jsonl("你a地!", &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"c\":\"你\",\"py\":[\"nǐ\"]}\n{\"c\":\"地\",\"py\":[\"de\",\"dì\"]}\n"
+        );
+    }
+
+    #[test]
+    fn test_push_json_string() {
+        let mut out = String::new();
+        push_json_string(&mut out, "a\"b\\c\n\u{1}");
+        assert_eq!(out, r#""a\"b\\c\n\u0001""#);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_convert_async_stream() {
+        init_test_map();
+        // A one-byte buffer splits every multi-byte character across reads
+        let text = "你好, world\n中国";
+        let reader = tokio::io::BufReader::with_capacity(1, text.as_bytes());
+        let mut output = Vec::new();
+        convert_async_stream(reader, &mut output, " ")
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            crate::to_pinyin_string(text, " ").unwrap()
+        );
+
+        let truncated = &"中".as_bytes()[..2];
+        let mut output = Vec::new();
+        assert!(
+            convert_async_stream(truncated, &mut output, " ")
+                .await
+                .is_err()
+        );
+    }
+}
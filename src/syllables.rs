@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::tone::{strip_tone, with_tone_of};
+
+/// The toneless syllables of standard Hanyu Pinyin, including the handful of
+/// interjection-only syllables (`hm`, `hng`, `m`, `n`, `ng`, `ê`).
+///
+/// `ü` is spelled out only where the orthography keeps it (after `n` and `l`);
+/// after `j`, `q`, `x` and `y` it is written as `u`, as in standard pinyin.
+#[rustfmt::skip]
+pub static SYLLABLES: &[&str] = &[
+    "a", "ai", "an", "ang", "ao",
+    "ba", "bai", "ban", "bang", "bao", "bei", "ben", "beng", "bi", "bian", "biao", "bie", "bin",
+    "bing", "bo", "bu",
+    "ca", "cai", "can", "cang", "cao", "ce", "cen", "ceng", "ci", "cong", "cou", "cu", "cuan",
+    "cui", "cun", "cuo",
+    "cha", "chai", "chan", "chang", "chao", "che", "chen", "cheng", "chi", "chong", "chou", "chu",
+    "chua", "chuai", "chuan", "chuang", "chui", "chun", "chuo",
+    "da", "dai", "dan", "dang", "dao", "de", "dei", "den", "deng", "di", "dia", "dian", "diao",
+    "die", "ding", "diu", "dong", "dou", "du", "duan", "dui", "dun", "duo",
+    "e", "ei", "en", "eng", "er", "ê",
+    "fa", "fan", "fang", "fei", "fen", "feng", "fiao", "fo", "fou", "fu",
+    "ga", "gai", "gan", "gang", "gao", "ge", "gei", "gen", "geng", "gong", "gou", "gu", "gua",
+    "guai", "guan", "guang", "gui", "gun", "guo",
+    "ha", "hai", "han", "hang", "hao", "he", "hei", "hen", "heng", "hm", "hng", "hong", "hou",
+    "hu", "hua", "huai", "huan", "huang", "hui", "hun", "huo",
+    "ji", "jia", "jian", "jiang", "jiao", "jie", "jin", "jing", "jiong", "jiu", "ju", "juan",
+    "jue", "jun",
+    "ka", "kai", "kan", "kang", "kao", "ke", "kei", "ken", "keng", "kong", "kou", "ku", "kua",
+    "kuai", "kuan", "kuang", "kui", "kun", "kuo",
+    "la", "lai", "lan", "lang", "lao", "le", "lei", "leng", "li", "lia", "lian", "liang", "liao",
+    "lie", "lin", "ling", "liu", "lo", "long", "lou", "lu", "luan", "lun", "luo", "lü", "lüe",
+    "m", "ma", "mai", "man", "mang", "mao", "me", "mei", "men", "meng", "mi", "mian", "miao",
+    "mie", "min", "ming", "miu", "mo", "mou", "mu",
+    "n", "na", "nai", "nan", "nang", "nao", "ne", "nei", "nen", "neng", "ng", "ni", "nian",
+    "niang", "niao", "nie", "nin", "ning", "niu", "nong", "nou", "nu", "nuan", "nun", "nuo", "nü",
+    "nüe",
+    "o", "ou",
+    "pa", "pai", "pan", "pang", "pao", "pei", "pen", "peng", "pi", "pian", "piao", "pie", "pin",
+    "ping", "po", "pou", "pu",
+    "qi", "qia", "qian", "qiang", "qiao", "qie", "qin", "qing", "qiong", "qiu", "qu", "quan",
+    "que", "qun",
+    "ran", "rang", "rao", "re", "ren", "reng", "ri", "rong", "rou", "ru", "rua", "ruan", "rui",
+    "run", "ruo",
+    "sa", "sai", "san", "sang", "sao", "se", "sen", "seng", "si", "song", "sou", "su", "suan",
+    "sui", "sun", "suo",
+    "sha", "shai", "shan", "shang", "shao", "she", "shei", "shen", "sheng", "shi", "shou", "shu",
+    "shua", "shuai", "shuan", "shuang", "shui", "shun", "shuo",
+    "ta", "tai", "tan", "tang", "tao", "te", "tei", "teng", "ti", "tian", "tiao", "tie", "ting",
+    "tong", "tou", "tu", "tuan", "tui", "tun", "tuo",
+    "wa", "wai", "wan", "wang", "wei", "wen", "weng", "wo", "wu",
+    "xi", "xia", "xian", "xiang", "xiao", "xie", "xin", "xing", "xiong", "xiu", "xu", "xuan",
+    "xue", "xun",
+    "ya", "yan", "yang", "yao", "ye", "yi", "yin", "ying", "yo", "yong", "you", "yu", "yuan",
+    "yue", "yun",
+    "za", "zai", "zan", "zang", "zao", "ze", "zei", "zen", "zeng", "zi", "zong", "zou", "zu",
+    "zuan", "zui", "zun", "zuo",
+    "zha", "zhai", "zhan", "zhang", "zhao", "zhe", "zhei", "zhen", "zheng", "zhi", "zhong",
+    "zhou", "zhu", "zhua", "zhuai", "zhuan", "zhuang", "zhui", "zhun", "zhuo",
+];
+
+static SYLLABLE_TRIE: OnceLock<SyllableTrie> = OnceLock::new();
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: BTreeMap<char, TrieNode>,
+    terminal: bool,
+}
+
+/// A prefix tree over the valid syllable inventory, used for as-you-type completion.
+#[derive(Debug, Default)]
+pub struct SyllableTrie {
+    root: TrieNode,
+}
+
+impl SyllableTrie {
+    /// Builds a trie from an arbitrary list of syllables.
+    pub fn new(syllables: &[&str]) -> Self {
+        let mut trie = SyllableTrie::default();
+        for syllable in syllables {
+            trie.insert(syllable);
+        }
+        trie
+    }
+
+    fn insert(&mut self, syllable: &str) {
+        let mut node = &mut self.root;
+        for c in syllable.chars() {
+            node = node.children.entry(c).or_default();
+        }
+        node.terminal = true;
+    }
+
+    /// Returns every syllable starting with `prefix`, in alphabetical order.
+    /// A `v` in the prefix is read as `ü`, matching the usual keyboard convention.
+    pub fn complete_prefix(&self, prefix: &str) -> Vec<String> {
+        let prefix: String = prefix
+            .chars()
+            .map(|c| {
+                if c == 'v' {
+                    'ü'
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect();
+
+        let mut node = &self.root;
+        for c in prefix.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return Vec::new(),
+            }
+        }
+
+        let mut results = Vec::new();
+        let mut current = prefix;
+        collect(node, &mut current, &mut results);
+        results
+    }
+
+    /// Returns `true` if `syllable` is a complete syllable in the trie.
+    pub fn contains(&self, syllable: &str) -> bool {
+        let mut node = &self.root;
+        for c in syllable.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        node.terminal
+    }
+}
+
+fn collect(node: &TrieNode, current: &mut String, results: &mut Vec<String>) {
+    if node.terminal {
+        results.push(current.clone());
+    }
+    for (&c, child) in &node.children {
+        current.push(c);
+        collect(child, current, results);
+        current.pop();
+    }
+}
+
+/// Returns the trie of the valid syllable inventory, building it on first use.
+pub fn syllable_trie() -> &'static SyllableTrie {
+    SYLLABLE_TRIE.get_or_init(|| SyllableTrie::new(SYLLABLES))
+}
+
+/// Returns all valid syllables starting with `prefix`.
+pub fn complete_prefix(prefix: &str) -> Vec<String> {
+    syllable_trie().complete_prefix(prefix)
+}
+
+/// Returns `true` if `syllable` is a valid toneless pinyin syllable.
+pub fn is_valid_syllable(syllable: &str) -> bool {
+    syllable_trie().contains(syllable)
+}
+
+/// Restores the full final that pinyin spelling elides after an initial consonant:
+/// `iu` -> `iou`, `ui` -> `uei`, `un` -> `uen` (`"jiu"` -> `"jiou"`, `"guì"` -> `"guèi"`,
+/// `"lun2"` -> `"luen2"`). `un` after `j`, `q` and `x` is `ün` and stays as it is, as do
+/// syllables without an initial consonant (`you`, `wei`, `wen` already spell the full final).
+/// The tone is kept in the form the input uses.
+pub fn expand_final(syllable: &str) -> String {
+    let toneless = strip_tone(syllable);
+    let (initial, rest) = split_initial(&toneless);
+    let expanded = match (initial, rest) {
+        ("" | "y" | "w", _) | ("j" | "q" | "x", "un") => return syllable.to_string(),
+        (_, "iu") => "iou",
+        (_, "ui") => "uei",
+        (_, "un") => "uen",
+        _ => return syllable.to_string(),
+    };
+    with_tone_of(syllable, &format!("{}{}", initial, expanded))
+}
+
+/// The inverse of [`expand_final`]: writes `iou`, `uei` and `uen` after an initial consonant
+/// in their standard spelled forms `iu`, `ui` and `un` (`"jiou"` -> `"jiu"`).
+pub fn contract_final(syllable: &str) -> String {
+    let toneless = strip_tone(syllable);
+    let (initial, rest) = split_initial(&toneless);
+    let contracted = match (initial, rest) {
+        ("" | "y" | "w", _) => return syllable.to_string(),
+        (_, "iou") => "iu",
+        (_, "uei") => "ui",
+        (_, "uen") => "un",
+        _ => return syllable.to_string(),
+    };
+    with_tone_of(syllable, &format!("{}{}", initial, contracted))
+}
+
+/// Splits a toneless syllable into its spelled initial and the rest. `y` and `w` count as
+/// initials here; [`full_final`] restores the final they stand for.
+pub(crate) fn split_initial(syllable: &str) -> (&str, &str) {
+    if matches!(syllable, "hm" | "hng" | "ng") {
+        return ("", syllable);
+    }
+    let len = if ["zh", "ch", "sh"].iter().any(|i| syllable.starts_with(i)) {
+        2
+    } else if syllable.starts_with(|c| "bpmfdtnlgkhjqxrzcsyw".contains(c)) && syllable.len() > 1 {
+        1
+    } else {
+        0
+    };
+    syllable.split_at(len)
+}
+
+/// Restores the full final that pinyin abbreviates (`iu`, `ui`, `un`) or hides behind `y` and
+/// `w`, given the spelled initial and rest from [`split_initial`] with `ü` made explicit by
+/// [`normalize_u`](crate::normalize_u): `("y", "ao")` -> `"iao"`, `("d", "ui")` -> `"uei"`.
+pub(crate) fn full_final(initial: &str, rest: &str) -> String {
+    match initial {
+        "y" if rest.starts_with(['i', 'ü']) => rest.to_string(),
+        "y" if rest == "ou" => "iou".to_string(),
+        "y" => format!("i{}", rest),
+        "w" if rest == "u" => "u".to_string(),
+        "w" if rest == "ei" => "uei".to_string(),
+        "w" if rest == "en" => "uen".to_string(),
+        "w" => format!("u{}", rest),
+        _ => match rest {
+            "iu" => "iou".to_string(),
+            "ui" => "uei".to_string(),
+            "un" => "uen".to_string(),
+            other => other.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_prefix() {
+        assert_eq!(
+            complete_prefix("zhua"),
+            vec!["zhua", "zhuai", "zhuan", "zhuang"]
+        );
+        assert_eq!(complete_prefix("nv"), vec!["nü", "nüe"]);
+        assert_eq!(complete_prefix("Lia"), vec!["lia", "lian", "liang", "liao"]);
+        assert!(complete_prefix("xyz").is_empty());
+        assert_eq!(complete_prefix("").len(), SYLLABLES.len());
+    }
+
+    #[test]
+    fn test_is_valid_syllable() {
+        assert!(is_valid_syllable("shuang"));
+        assert!(is_valid_syllable("lüe"));
+        assert!(!is_valid_syllable("shua1"));
+        assert!(!is_valid_syllable("zhi "));
+        assert!(!is_valid_syllable("zh"));
+    }
+
+    #[test]
+    fn test_expand_and_contract_final() {
+        let pairs = [
+            ("jiu", "jiou"),
+            ("guì", "guèi"),
+            ("lun2", "luen2"),
+            ("diū", "diōu"),
+            ("zhǔn", "zhuěn"),
+        ];
+        for (spelled, full) in pairs {
+            assert_eq!(expand_final(spelled), full);
+            assert_eq!(contract_final(full), spelled);
+        }
+
+        // Nothing is elided in these
+        for syllable in ["jun", "yǒu", "wei", "wen4", "hǎo", "m"] {
+            assert_eq!(expand_final(syllable), syllable);
+            assert_eq!(contract_final(syllable), syllable);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::han::is_cjk_ideograph;
+use crate::{PinyinError, pinyin_map};
+
+/// Lays `text` out as a study sheet: each row of hanzi is printed with its pinyin on the line
+/// beneath, every character and its first reading left-aligned in a shared column, and rows
+/// wrapped so no line is wider than `cols` terminal columns. Hanzi, kana, hangul and full-width
+/// forms count as two columns, everything else as one; a character wider than `cols` still gets
+/// a row of its own.
+///
+/// Columns are separated by one space and trailing spaces are trimmed. Each pair of lines ends
+/// with a newline, pairs are separated by a blank line, and a newline in `text` starts a new
+/// pair. Characters without a reading have an empty pinyin cell. For example, 你好吗 at 8
+/// columns gives `"你 好\nnǐ hǎo\n\n吗\nma\n"`.
+pub fn to_aligned_table(text: &str, cols: usize) -> Result<String, PinyinError> {
+    let map = pinyin_map()?;
+    let mut rows: Vec<Vec<(char, &str)>> = Vec::new();
+    let mut row = Vec::new();
+    let mut row_width = 0;
+
+    for c in text.chars() {
+        if c == '\n' {
+            rows.push(std::mem::take(&mut row));
+            row_width = 0;
+            continue;
+        }
+        if c.is_control() {
+            continue;
+        }
+        let pinyin = map
+            .get(&(c as u32))
+            .and_then(|p_vec| p_vec.first())
+            .map_or("", String::as_str);
+        let width = cell_width(c, pinyin);
+        if !row.is_empty() && row_width + 1 + width > cols {
+            rows.push(std::mem::take(&mut row));
+            row_width = 0;
+        }
+        row_width += if row.is_empty() { width } else { 1 + width };
+        row.push((c, pinyin));
+    }
+    rows.push(row);
+
+    let blocks: Vec<String> = rows
+        .iter()
+        .filter(|row| !row.is_empty())
+        .map(|row| {
+            let mut hanzi = String::new();
+            let mut pinyin = String::new();
+            for &(c, reading) in row {
+                let width = cell_width(c, reading);
+                hanzi.push(c);
+                pad(&mut hanzi, width - char_width(c) + 1);
+                pinyin.push_str(reading);
+                pad(&mut pinyin, width - reading.chars().count() + 1);
+            }
+            format!("{}\n{}\n", hanzi.trim_end(), pinyin.trim_end())
+        })
+        .collect();
+    Ok(blocks.join("\n"))
+}
+
+fn cell_width(c: char, pinyin: &str) -> usize {
+    char_width(c).max(pinyin.chars().count())
+}
+
+fn pad(line: &mut String, spaces: usize) {
+    line.extend(std::iter::repeat_n(' ', spaces));
+}
+
+/// Terminal columns taken by `c`: two for East Asian wide characters, one otherwise.
+fn char_width(c: char) -> usize {
+    let wide = is_cjk_ideograph(c as u32)
+        || matches!(
+            c,
+            '\u{1100}'..='\u{115F}'
+                | '\u{2E80}'..='\u{303E}'
+                | '\u{3041}'..='\u{33FF}'
+                | '\u{AC00}'..='\u{D7A3}'
+                | '\u{FE30}'..='\u{FE4F}'
+                | '\u{FF01}'..='\u{FF60}'
+                | '\u{FFE0}'..='\u{FFE6}'
+        );
+    if wide { 2 } else { 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_to_aligned_table() {
+        init_test_map();
+        assert_eq!(
+            to_aligned_table("中国人a", 80).unwrap(),
+            "中    国  人  a\nzhōng guó rén\n"
+        );
+        // Wrapped to 10 columns: the cells of 中 and 国 take 9, so 人 starts a new row
+        assert_eq!(
+            to_aligned_table("中国人", 10).unwrap(),
+            "中    国\nzhōng guó\n\n人\nrén\n"
+        );
+        assert_eq!(
+            to_aligned_table("你好\n世界", 80).unwrap(),
+            "你 好\nnǐ hǎo\n\n世  界\nshì jiè\n"
+        );
+        // Full-width punctuation is double width and has no reading
+        assert_eq!(to_aligned_table("好！", 80).unwrap(), "好  ！\nhǎo\n");
+        assert_eq!(to_aligned_table("", 80).unwrap(), "");
+    }
+}
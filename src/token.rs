@@ -0,0 +1,120 @@
+use crate::{PinyinError, is_chinese_char, pinyin_map};
+
+/// A character of the input, tagged by whether it is a Han ideograph (see [`is_chinese_char`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// A Han ideograph with its readings in the map's order, empty if the map has none.
+    Chinese {
+        ch: char,
+        readings: &'static [String],
+    },
+    /// Any other character, passed through unchanged.
+    Other(char),
+}
+
+/// Lazily tags every character of `text` as a [`Token`], so a renderer can start emitting
+/// output before the whole input has been looked up.
+pub fn pinyin_tokens(text: &str) -> Result<impl Iterator<Item = Token> + '_, PinyinError> {
+    let map = pinyin_map()?;
+    Ok(text.chars().map(move |ch| {
+        if is_chinese_char(ch) {
+            let readings = map.get(&(ch as u32)).map_or(&[][..], Vec::as_slice);
+            Token::Chinese { ch, readings }
+        } else {
+            Token::Other(ch)
+        }
+    }))
+}
+
+/// A maximal span of the input in one script, as produced by [`to_pinyin_runs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Run {
+    /// Han ideographs, with the first reading of each (`None` if the map has none).
+    Han {
+        text: String,
+        pinyin: Vec<Option<String>>,
+    },
+    /// Everything else (Latin, kana, hangul, punctuation, whitespace), verbatim.
+    Other(String),
+}
+
+/// Splits `text` into alternating runs of Han ideographs (see [`is_chinese_char`]) and other
+/// text, so multilingual renderers can apply a different font or treatment to each run. Han
+/// runs carry their pinyin.
+pub fn to_pinyin_runs(text: &str) -> Result<Vec<Run>, PinyinError> {
+    let map = pinyin_map()?;
+    let mut runs = Vec::new();
+    for c in text.chars() {
+        if !is_chinese_char(c) {
+            match runs.last_mut() {
+                Some(Run::Other(other)) => other.push(c),
+                _ => runs.push(Run::Other(c.to_string())),
+            }
+            continue;
+        }
+        let reading = map
+            .get(&(c as u32))
+            .and_then(|p_vec| p_vec.first())
+            .cloned();
+        match runs.last_mut() {
+            Some(Run::Han { text, pinyin }) => {
+                text.push(c);
+                pinyin.push(reading);
+            }
+            _ => runs.push(Run::Han {
+                text: c.to_string(),
+                pinyin: vec![reading],
+            }),
+        }
+    }
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_pinyin_tokens() {
+        init_test_map();
+        let tokens: Vec<Token> = pinyin_tokens("中a地").unwrap().collect();
+        assert_eq!(tokens.len(), 3);
+        assert!(
+            matches!(tokens[0], Token::Chinese { ch: '中', readings } if readings == ["zhōng"])
+        );
+        assert_eq!(tokens[1], Token::Other('a'));
+        assert!(
+            matches!(tokens[2], Token::Chinese { ch: '地', readings } if readings == ["de", "dì"])
+        );
+
+        // An ideograph the map has no reading for is still Chinese
+        assert!(!crate::is_mapped('\u{F900}').unwrap());
+        let tokens: Vec<Token> = pinyin_tokens("\u{F900}").unwrap().collect();
+        assert_eq!(
+            tokens,
+            vec![Token::Chinese {
+                ch: '\u{F900}',
+                readings: &[]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_to_pinyin_runs() {
+        init_test_map();
+        let runs = to_pinyin_runs("Hello中国, ひらがな한").unwrap();
+        assert_eq!(
+            runs,
+            vec![
+                Run::Other("Hello".to_string()),
+                Run::Han {
+                    text: "中国".to_string(),
+                    pinyin: vec![Some("zhōng".to_string()), Some("guó".to_string())],
+                },
+                Run::Other(", ひらがな한".to_string()),
+            ]
+        );
+        assert!(to_pinyin_runs("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+/// How the tone of a syllable is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneStyle {
+    /// Tone marks over the vowel: `"nǚ"`, `"ma"` for the neutral tone.
+    #[default]
+    Diacritic,
+    /// A tone number after the syllable, with `v` for `ü`: `"nv3"`, `"ma5"` for the neutral tone.
+    Numbered,
+    /// No tone at all: `"nü"`, `"ma"`.
+    Toneless,
+}
+
+/// Rewrites a syllable written with tone marks or a trailing tone number in the given style,
+/// e.g. `"nǚ"` -> `"nv3"` and `"hao3"` -> `"hǎo"`.
+pub fn to_tone_style(pinyin: &str, style: ToneStyle) -> String {
+    render_syllable(&strip_tone(pinyin), tone_number(pinyin), style)
+}
+
+/// Renders a toneless syllable (which may spell `ü` as `v`) with `tone` in `style`.
+pub(crate) fn render_syllable(toneless: &str, tone: u8, style: ToneStyle) -> String {
+    match style {
+        ToneStyle::Diacritic => place_tone(toneless, tone),
+        ToneStyle::Numbered => format!("{}{}", toneless.replace('ü', "v"), tone),
+        ToneStyle::Toneless => toneless.replace('v', "ü"),
+    }
+}
+
+/// Writes the tone of `original` onto `toneless` the same way `original` writes it: as a
+/// trailing number, as a tone mark, or not at all.
+pub(crate) fn with_tone_of(original: &str, toneless: &str) -> String {
+    match original.chars().last() {
+        Some(digit) if digit.is_ascii_digit() => format!("{}{}", toneless, digit),
+        _ if strip_tone(original) == original => toneless.to_string(),
+        _ => place_tone(toneless, tone_number(original)),
+    }
+}
+
+/// Removes tone marks and a trailing tone number from a pinyin string, e.g.
+/// `"xiāng"` -> `"xiang"`, `"nǚ"` -> `"nü"`, `"ju3"` -> `"ju"`.
+///
+/// `ü` keeps its diaeresis; use [`normalize_u`] to make the `ü` hidden after `j`, `q`, `x`
+/// and `y` explicit as well.
+pub fn strip_tone(pinyin: &str) -> String {
+    let mut result: String = pinyin.chars().map(toneless_char).collect();
+    if result.ends_with(|c: char| ('0'..='5').contains(&c)) {
+        result.pop();
+    }
+    result
+}
+
+/// Rewrites every `ü` that standard orthography hides as a plain `u` into an explicit `ü`,
+/// keeping any tone mark: after `j`, `q`, `x` and `y` the written `u` is always `ü`
+/// (`"ju"` -> `"jü"`, `"quē"` -> `"qüē"`, `"xǔ"` -> `"xǚ"`), and the keyboard spelling `v` is
+/// read as `ü` (`"nv3"` -> `"nü3"`).
+///
+/// Syllables where `u` really is `u` (`"nu"`, `"lù"`, `"zhu"`) are unchanged, so
+/// `normalize_u(&strip_tone(reading))` distinguishes 居 (`"jü"`) from 女 (`"nü"`) and
+/// 奴 (`"nu"`).
+pub fn normalize_u(pinyin: &str) -> String {
+    let mut result = String::with_capacity(pinyin.len() + 1);
+    let mut prev: Option<char> = None;
+    for c in pinyin.chars() {
+        let replaced = match (prev.map(|p| p.to_ascii_lowercase()), c) {
+            (_, 'v') => 'ü',
+            (_, 'V') => 'Ü',
+            (Some('j' | 'q' | 'x' | 'y'), _) => u_to_umlaut(c).unwrap_or(c),
+            _ => c,
+        };
+        result.push(replaced);
+        prev = Some(c);
+    }
+    result
+}
+
+/// Reduces a reading to a stable lookup key: lowercase, without tone, and with every `ü`
+/// written explicitly (see [`normalize_u`]), so `"Nǚ"`, `"nv3"` and `"nü"` all give `"nü"`
+/// and `"jū"` gives `"jü"`.
+pub fn canonical_key(pinyin: &str) -> String {
+    normalize_u(&strip_tone(&pinyin.to_lowercase()))
+}
+
+/// Returns the tone of a pinyin syllable written with a tone mark or a trailing tone number:
+/// 1 to 4 for the four tones and 5 for the neutral tone (no mark, or an explicit `5` or `0`).
+pub fn tone_number(pinyin: &str) -> u8 {
+    if let Some(digit) = pinyin.chars().last().and_then(|c| c.to_digit(10)) {
+        return match digit {
+            1..=4 => digit as u8,
+            _ => 5,
+        };
+    }
+    pinyin.chars().find_map(tone_of_char).unwrap_or(5)
+}
+
+/// Puts the tone mark for `tone` (1 to 4) on the vowel that carries it in standard
+/// orthography: `a` or `e` if present, the `o` of `ou`, otherwise the last vowel. Syllables
+/// without a vowel (`m`, `n`, `ng`) take the mark on their first letter. An erhua `r`
+/// (`"huar"`) never takes the mark. `v` is written as `ü`. Any other tone (neutral) returns
+/// the syllable unmarked.
+///
+/// This is the placement rule used throughout the crate, exposed so downstream renderers can
+/// rely on it instead of reimplementing it:
+///
+/// ```
+/// use mandarin_to_pinyin::place_tone;
+///
+/// assert_eq!(place_tone("hao", 3), "hǎo");
+/// assert_eq!(place_tone("gui", 4), "guì");
+/// assert_eq!(place_tone("lve", 4), "lüè");
+/// ```
+pub fn place_tone(toneless: &str, tone: u8) -> String {
+    let chars: Vec<char> = toneless
+        .chars()
+        .map(|c| match c {
+            'v' => 'ü',
+            'V' => 'Ü',
+            c => c,
+        })
+        .collect();
+    if !(1..=4).contains(&tone) {
+        return chars.into_iter().collect();
+    }
+
+    // Vowels are found case-insensitively, so "Ai" is marked like "ai"
+    let lower: Vec<char> = chars.iter().map(|&c| to_lower(c)).collect();
+    let position = lower
+        .iter()
+        .position(|&c| c == 'a' || c == 'e')
+        .or_else(|| lower.windows(2).position(|pair| pair == ['o', 'u']))
+        .or_else(|| {
+            lower
+                .iter()
+                .rposition(|&c| matches!(c, 'i' | 'o' | 'u' | 'ü'))
+        })
+        .or_else(|| lower.iter().position(|&c| c == 'm' || c == 'n'));
+
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            if Some(i) == position {
+                mark_char(c, tone).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Rewrites a syllable in decomposed (NFD) form: each tone mark becomes a combining mark after
+/// its base letter, and `ü` becomes `u` followed by a combining diaeresis, so `"lǜ"` is
+/// `"lu\u{0308}\u{0300}"`. Other characters are unchanged.
+pub(crate) fn decompose(pinyin: &str) -> String {
+    const MARKS: [char; 4] = ['\u{0304}', '\u{0301}', '\u{030C}', '\u{0300}'];
+
+    let mut result = String::with_capacity(pinyin.len() + 4);
+    for c in pinyin.chars() {
+        match toneless_char(c) {
+            'ü' => result.push_str("u\u{0308}"),
+            base => result.push(base),
+        }
+        if let Some(tone) = tone_of_char(c) {
+            result.push(MARKS[tone as usize - 1]);
+        }
+    }
+    result
+}
+
+/// Puts the mark for `tone` on `c`, keeping its case.
+fn mark_char(c: char, tone: u8) -> Option<char> {
+    let marked = mark_lowercase(to_lower(c), tone)?;
+    Some(if c.is_uppercase() {
+        to_upper(marked)
+    } else {
+        marked
+    })
+}
+
+fn mark_lowercase(c: char, tone: u8) -> Option<char> {
+    let marks: [char; 4] = match c {
+        'a' => ['ā', 'á', 'ǎ', 'à'],
+        'e' => ['ē', 'é', 'ě', 'è'],
+        'i' => ['ī', 'í', 'ǐ', 'ì'],
+        'o' => ['ō', 'ó', 'ǒ', 'ò'],
+        'u' => ['ū', 'ú', 'ǔ', 'ù'],
+        'ü' => ['ǖ', 'ǘ', 'ǚ', 'ǜ'],
+        'm' if tone == 2 => return Some('ḿ'),
+        'n' if tone >= 2 => return Some(['ń', 'ň', 'ǹ'][tone as usize - 2]),
+        _ => return None,
+    };
+    Some(marks[tone as usize - 1])
+}
+
+fn tone_of_char(c: char) -> Option<u8> {
+    Some(match to_lower(c) {
+        'ā' | 'ē' | 'ī' | 'ō' | 'ū' | 'ǖ' => 1,
+        'á' | 'é' | 'í' | 'ó' | 'ú' | 'ǘ' | 'ḿ' | 'ń' => 2,
+        'ǎ' | 'ě' | 'ǐ' | 'ǒ' | 'ǔ' | 'ǚ' | 'ň' => 3,
+        'à' | 'è' | 'ì' | 'ò' | 'ù' | 'ǜ' | 'ǹ' => 4,
+        _ => return None,
+    })
+}
+
+fn u_to_umlaut(c: char) -> Option<char> {
+    Some(match c {
+        'u' => 'ü',
+        'ū' => 'ǖ',
+        'ú' => 'ǘ',
+        'ǔ' => 'ǚ',
+        'ù' => 'ǜ',
+        _ => return None,
+    })
+}
+
+fn toneless_char(c: char) -> char {
+    let base = toneless_lowercase(to_lower(c));
+    if c.is_uppercase() {
+        to_upper(base)
+    } else {
+        base
+    }
+}
+
+fn toneless_lowercase(c: char) -> char {
+    match c {
+        'ā' | 'á' | 'ǎ' | 'à' => 'a',
+        'ē' | 'é' | 'ě' | 'è' => 'e',
+        'ī' | 'í' | 'ǐ' | 'ì' => 'i',
+        'ō' | 'ó' | 'ǒ' | 'ò' => 'o',
+        'ū' | 'ú' | 'ǔ' | 'ù' => 'u',
+        'ǖ' | 'ǘ' | 'ǚ' | 'ǜ' => 'ü',
+        'ḿ' => 'm',
+        'ń' | 'ň' | 'ǹ' => 'n',
+        _ => c,
+    }
+}
+
+/// Lowercases a letter that has a single-character lowercase form, as every pinyin letter does.
+fn to_lower(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn to_upper(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{init_test_map, lookup_chars_vec_for_str};
+
+    #[test]
+    fn test_strip_tone() {
+        assert_eq!(strip_tone("xiāng"), "xiang");
+        assert_eq!(strip_tone("nǚ"), "nü");
+        assert_eq!(strip_tone("ju3"), "ju");
+        assert_eq!(strip_tone("ma"), "ma");
+        assert_eq!(strip_tone("ǹg"), "ng");
+    }
+
+    #[test]
+    fn test_tone_number() {
+        assert_eq!(tone_number("xiāng"), 1);
+        assert_eq!(tone_number("nǚ"), 3);
+        assert_eq!(tone_number("ǹg"), 4);
+        assert_eq!(tone_number("hao3"), 3);
+        assert_eq!(tone_number("ma"), 5);
+        assert_eq!(tone_number("ma5"), 5);
+        assert_eq!(tone_number("ma0"), 5);
+    }
+
+    #[test]
+    fn test_place_tone() {
+        assert_eq!(place_tone("hao", 3), "hǎo");
+        assert_eq!(place_tone("xie", 4), "xiè");
+        assert_eq!(place_tone("zhou", 1), "zhōu");
+        assert_eq!(place_tone("gui", 4), "guì");
+        assert_eq!(place_tone("liu", 2), "liú");
+        assert_eq!(place_tone("lve", 4), "lüè");
+        assert_eq!(place_tone("nv", 3), "nǚ");
+        assert_eq!(place_tone("ng", 4), "ǹg");
+        assert_eq!(place_tone("m", 2), "ḿ");
+        assert_eq!(place_tone("ma", 5), "ma");
+        assert_eq!(place_tone("huar", 1), "huār");
+        // Capitalized syllables keep their case and take the mark on the same vowel
+        assert_eq!(place_tone("Ai", 4), "Ài");
+        assert_eq!(place_tone("Ou", 3), "Ǒu");
+        assert_eq!(place_tone("Lve", 4), "Lüè");
+        assert_eq!(place_tone("Ng", 2), "Ńg");
+    }
+
+    #[test]
+    fn test_to_tone_style() {
+        assert_eq!(to_tone_style("nǚ", ToneStyle::Numbered), "nv3");
+        assert_eq!(to_tone_style("nǚ", ToneStyle::Toneless), "nü");
+        assert_eq!(to_tone_style("hao3", ToneStyle::Diacritic), "hǎo");
+        assert_eq!(to_tone_style("lve4", ToneStyle::Diacritic), "lüè");
+        assert_eq!(to_tone_style("ma", ToneStyle::Numbered), "ma5");
+        assert_eq!(to_tone_style("ma5", ToneStyle::Diacritic), "ma");
+        assert_eq!(to_tone_style("Ai4", ToneStyle::Diacritic), "Ài");
+        assert_eq!(to_tone_style("An1", ToneStyle::Diacritic), "Ān");
+        assert_eq!(to_tone_style("Ou3", ToneStyle::Diacritic), "Ǒu");
+        assert_eq!(to_tone_style("Ǒu", ToneStyle::Numbered), "Ou3");
+    }
+
+    #[test]
+    fn test_decompose() {
+        assert_eq!(decompose("hǎo"), "ha\u{030C}o");
+        assert_eq!(decompose("lǜ"), "lu\u{0308}\u{0300}");
+        assert_eq!(decompose("nü"), "nu\u{0308}");
+        assert_eq!(decompose("ǹg"), "n\u{0300}g");
+        assert_eq!(decompose("ma"), "ma");
+    }
+
+    #[test]
+    fn test_normalize_u() {
+        assert_eq!(normalize_u("ju"), "jü");
+        assert_eq!(normalize_u("quē"), "qüē");
+        assert_eq!(normalize_u("xuan"), "xüan");
+        assert_eq!(normalize_u("yù"), "yǜ");
+        assert_eq!(normalize_u("nv3"), "nü3");
+        assert_eq!(normalize_u("lù"), "lù");
+        assert_eq!(normalize_u("zhu"), "zhu");
+        assert_eq!(normalize_u("jiu"), "jiu");
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        assert_eq!(canonical_key("Nǚ"), "nü");
+        assert_eq!(canonical_key("nv3"), "nü");
+        assert_eq!(canonical_key("jū"), "jü");
+        assert_eq!(canonical_key("hǎo"), "hao");
+    }
+
+    #[test]
+    fn test_ju_and_nv_readings() {
+        init_test_map();
+        let readings = lookup_chars_vec_for_str("居女奴").unwrap();
+        let first: Vec<&str> = readings
+            .iter()
+            .map(|r| r.as_ref().unwrap()[0].as_str())
+            .collect();
+        assert_eq!(first, vec!["jū", "nǚ", "nú"]);
+
+        // Tone stripping alone keeps the orthographic spelling
+        let toneless: Vec<String> = first.iter().map(|r| strip_tone(r)).collect();
+        assert_eq!(toneless, vec!["ju", "nü", "nu"]);
+
+        // ü-aware output reveals that 居 and 女 share the ü final, unlike 奴
+        let u_aware: Vec<String> = toneless.iter().map(|r| normalize_u(r)).collect();
+        assert_eq!(u_aware, vec!["jü", "nü", "nu"]);
+        assert_eq!(normalize_u(first[0]), "jǖ");
+    }
+}
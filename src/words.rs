@@ -0,0 +1,246 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::{PinyinError, pinyin_map};
+
+static WORD_TO_PINYIN: OnceLock<WordMap> = OnceLock::new();
+
+#[derive(Debug)]
+pub(crate) struct WordMap {
+    words: HashMap<String, Vec<String>>,
+    max_len: usize,
+}
+
+/// A span of the input produced by word segmentation.
+pub(crate) struct Segment<'a> {
+    /// Index of the first character of the segment.
+    pub start: usize,
+    /// Number of characters in the segment.
+    pub len: usize,
+    /// Per-syllable readings when the segment is a dictionary word.
+    pub readings: Option<&'a Vec<String>>,
+}
+
+/// Initializes the global word dictionary, mapping multi-character words to one reading per
+/// character (e.g. `"银行"` -> `["yín", "háng"]`). Word-aware functions use it to segment text
+/// and to override per-character readings; without it they fall back to single characters.
+///
+/// Returns an error if a word's reading count does not match its character count, or if the
+/// dictionary has already been initialized.
+pub fn init_word_map(
+    words: HashMap<String, Vec<String>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    WORD_TO_PINYIN
+        .set(WordMap::new(words)?)
+        .map_err(|_| "failed to set word mappings in OnceLock")?;
+
+    Ok(())
+}
+
+/// Segments `text` into words using the dictionary loaded with [`init_word_map`], returning
+/// each word's text with one reading per syllable. Dictionary words take their dictionary
+/// readings; every other character is its own segment with its first reading, or no readings
+/// if it has none. Without a dictionary every character is its own segment.
+pub fn segment_words(text: &str) -> Result<Vec<(String, Vec<String>)>, PinyinError> {
+    Ok(segment_words_with(word_map(), pinyin_map()?, text))
+}
+
+/// [`segment_words`] against the given dictionary (`None` for none) and character map.
+fn segment_words_with(
+    words: Option<&WordMap>,
+    map: &HashMap<u32, Vec<String>>,
+    text: &str,
+) -> Vec<(String, Vec<String>)> {
+    let chars: Vec<char> = text.chars().collect();
+    segment(words, &chars)
+        .into_iter()
+        .map(|segment| {
+            let word: String = chars[segment.start..segment.start + segment.len]
+                .iter()
+                .collect();
+            let readings = match segment.readings {
+                Some(readings) => readings.clone(),
+                None => map
+                    .get(&(chars[segment.start] as u32))
+                    .and_then(|p_vec| p_vec.first())
+                    .into_iter()
+                    .cloned()
+                    .collect(),
+            };
+            (word, readings)
+        })
+        .collect()
+}
+
+/// Finds the longest prefix of `text` that is a word in the dictionary loaded with
+/// [`init_word_map`], falling back to the first character alone if it has a reading. Returns
+/// the prefix length in bytes and one reading per character (the first reading of a single
+/// character), or `None` if `text` starts with neither a dictionary word nor a mapped
+/// character.
+///
+/// This is the matching step [`segment_words`] applies at each position, exposed for building
+/// custom segmenters.
+pub fn longest_mapped_prefix(text: &str) -> Result<Option<(usize, Vec<String>)>, PinyinError> {
+    Ok(longest_mapped_prefix_with(word_map(), pinyin_map()?, text))
+}
+
+/// [`longest_mapped_prefix`] against the given dictionary (`None` for none) and character map.
+fn longest_mapped_prefix_with(
+    words: Option<&WordMap>,
+    map: &HashMap<u32, Vec<String>>,
+    text: &str,
+) -> Option<(usize, Vec<String>)> {
+    let max_len = words.map_or(1, |words| words.max_len.max(1));
+    let chars: Vec<char> = text.chars().take(max_len).collect();
+    let segment = segment(words, &chars).into_iter().next()?;
+
+    let byte_len = chars[..segment.len].iter().map(|c| c.len_utf8()).sum();
+    let readings = match segment.readings {
+        Some(readings) => readings.clone(),
+        None => vec![map.get(&(chars[0] as u32))?.first()?.clone()],
+    };
+    Some((byte_len, readings))
+}
+
+impl WordMap {
+    pub(crate) fn new(words: HashMap<String, Vec<String>>) -> Result<Self, String> {
+        let mut max_len = 0;
+        for (word, readings) in &words {
+            let len = word.chars().count();
+            if len != readings.len() {
+                return Err(format!(
+                    "word '{}' has {} characters but {} readings",
+                    word,
+                    len,
+                    readings.len()
+                ));
+            }
+            max_len = max_len.max(len);
+        }
+        Ok(WordMap { words, max_len })
+    }
+}
+
+pub(crate) fn word_map() -> Option<&'static WordMap> {
+    WORD_TO_PINYIN.get()
+}
+
+/// Splits `chars` into words by forward maximum matching against `words`.
+/// Characters not covered by a dictionary word become single-character segments; if no
+/// dictionary is given, every character is its own segment.
+pub(crate) fn segment<'a>(words: Option<&'a WordMap>, chars: &[char]) -> Vec<Segment<'a>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut matched = None;
+        if let Some(words) = words {
+            let longest = words.max_len.min(chars.len() - start);
+            for len in (2..=longest).rev() {
+                let candidate: String = chars[start..start + len].iter().collect();
+                if let Some(readings) = words.words.get(&candidate) {
+                    matched = Some(Segment {
+                        start,
+                        len,
+                        readings: Some(readings),
+                    });
+                    break;
+                }
+            }
+        }
+
+        let segment = matched.unwrap_or(Segment {
+            start,
+            len: 1,
+            readings: None,
+        });
+        start += segment.len;
+        segments.push(segment);
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_test_map;
+
+    #[test]
+    fn test_segment_words_without_dictionary() {
+        init_test_map();
+        // Other tests never load the global dictionary, so every character is its own word
+        assert_eq!(
+            segment_words("银行a").unwrap(),
+            vec![
+                ("银".to_string(), vec!["yín".to_string()]),
+                ("行".to_string(), vec!["xíng".to_string()]),
+                ("a".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_segment_words_with_dictionary() {
+        init_test_map();
+        let words = WordMap::new(HashMap::from([(
+            "银行".to_string(),
+            vec!["yín".to_string(), "háng".to_string()],
+        )]))
+        .unwrap();
+        assert_eq!(
+            segment_words_with(Some(&words), pinyin_map().unwrap(), "去银行a"),
+            vec![
+                ("去".to_string(), vec!["qù".to_string()]),
+                (
+                    "银行".to_string(),
+                    vec!["yín".to_string(), "háng".to_string()]
+                ),
+                ("a".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_longest_mapped_prefix_without_dictionary() {
+        init_test_map();
+        assert_eq!(
+            longest_mapped_prefix("银行").unwrap(),
+            Some((3, vec!["yín".to_string()]))
+        );
+        assert_eq!(longest_mapped_prefix("a银").unwrap(), None);
+        assert_eq!(longest_mapped_prefix("").unwrap(), None);
+    }
+
+    #[test]
+    fn test_longest_mapped_prefix_with_dictionary() {
+        init_test_map();
+        let words = WordMap::new(HashMap::from([(
+            "银行".to_string(),
+            vec!["yín".to_string(), "háng".to_string()],
+        )]))
+        .unwrap();
+        let map = pinyin_map().unwrap();
+        assert_eq!(
+            longest_mapped_prefix_with(Some(&words), map, "银行卡"),
+            Some((6, vec!["yín".to_string(), "háng".to_string()]))
+        );
+        assert_eq!(
+            longest_mapped_prefix_with(Some(&words), map, "卡"),
+            Some((3, vec!["kǎ".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_segment_with_dictionary() {
+        let words = WordMap::new(HashMap::from([(
+            "银行".to_string(),
+            vec!["yín".to_string(), "háng".to_string()],
+        )]))
+        .unwrap();
+        let chars: Vec<char> = "去银行".chars().collect();
+        let segments = segment(Some(&words), &chars);
+        let spans: Vec<(usize, usize)> = segments.iter().map(|s| (s.start, s.len)).collect();
+        assert_eq!(spans, vec![(0, 1), (1, 2)]);
+        assert_eq!(segments[1].readings.unwrap(), &["yín", "háng"]);
+    }
+}
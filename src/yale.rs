@@ -0,0 +1,150 @@
+use crate::is_valid_syllable;
+use crate::syllables::{full_final, split_initial};
+use crate::tone::{normalize_u, place_tone, strip_tone, tone_number};
+
+/// Converts a pinyin syllable to Yale Mandarin romanization, e.g. `"zhong1"` -> `"jung1"`,
+/// `"xiàng"` -> `"syàng"`, `"qu"` -> `"chyu"`. Returns `None` if `syllable` is not a valid
+/// pinyin syllable.
+///
+/// The tone is written the way the input writes it: a trailing tone number stays a trailing
+/// number, and a tone mark is placed on the main vowel of the Yale spelling (or, for the
+/// vowelless `jr`, `chr`, `shr`, `r`, `dz`, `tsz` and `sz`, as a combining mark on the last
+/// letter). A toneless syllable stays toneless.
+pub fn pinyin_to_yale(syllable: &str) -> Option<String> {
+    let lower = syllable.to_lowercase();
+    let toneless = strip_tone(&lower);
+    if !is_valid_syllable(&toneless.replace('v', "ü")) {
+        return None;
+    }
+    let yale = yale_spelling(&normalize_u(&toneless));
+
+    let tone = tone_number(&lower);
+    Some(match lower.chars().last() {
+        Some(digit) if digit.is_ascii_digit() => format!("{}{}", yale, digit),
+        _ if lower == toneless => yale,
+        _ => mark_yale(&yale, tone),
+    })
+}
+
+/// Spells a toneless syllable (with every `ü` explicit, as produced by `normalize_u`) in Yale.
+fn yale_spelling(pinyin: &str) -> String {
+    match pinyin {
+        "zhi" => return "jr".to_string(),
+        "chi" => return "chr".to_string(),
+        "shi" => return "shr".to_string(),
+        "ri" => return "r".to_string(),
+        "zi" => return "dz".to_string(),
+        "ci" => return "tsz".to_string(),
+        "si" => return "sz".to_string(),
+        "ê" => return "e".to_string(),
+        "er" | "m" | "n" | "ng" | "hm" | "hng" => return pinyin.to_string(),
+        _ => {}
+    }
+
+    let (initial, rest) = split_initial(pinyin);
+    let final_ = full_final(initial, rest);
+
+    let yale_initial = match initial {
+        "zh" | "j" => "j",
+        "q" => "ch",
+        "x" => "sy",
+        "z" => "dz",
+        "c" => "ts",
+        "y" | "w" => "",
+        other => other,
+    };
+    let yale_final = match (yale_initial, final_.as_str()) {
+        ("", "i") => "yi",
+        ("", "in") => "yin",
+        ("", "ing") => "ying",
+        ("", "u") => "wu",
+        ("", "uen") => "wen",
+        ("", "io") => "yo",
+        ("b" | "p" | "m" | "f", "o") => "wo",
+        (_, "ao") => "au",
+        (_, "ong") => "ung",
+        (_, "ia") => "ya",
+        (_, "ie") => "ye",
+        (_, "iao") => "yau",
+        (_, "iou") => "you",
+        (_, "ian") => "yan",
+        (_, "iang") => "yang",
+        (_, "iong") => "yung",
+        (_, "ua") => "wa",
+        (_, "uo") => "wo",
+        (_, "uai") => "wai",
+        (_, "uei") => "wei",
+        (_, "uan") => "wan",
+        (_, "uen") => "wun",
+        (_, "uang") => "wang",
+        (_, "ueng") => "weng",
+        (_, "ü") => "yu",
+        (_, "üe") => "ywe",
+        (_, "üan") => "ywan",
+        (_, "ün") => "yun",
+        (_, other) => other,
+    };
+
+    // `sy` already carries the glide: xia is sya, not syya
+    match yale_final.strip_prefix('y') {
+        Some(rest) if yale_initial == "sy" => format!("sy{}", rest),
+        _ => format!("{}{}", yale_initial, yale_final),
+    }
+}
+
+/// Puts the tone mark on a Yale syllable, using a combining mark when it has no vowel.
+fn mark_yale(yale: &str, tone: u8) -> String {
+    if !(1..=4).contains(&tone) || yale.contains(['a', 'e', 'i', 'o', 'u']) {
+        return place_tone(yale, tone);
+    }
+    let mark = ['\u{0304}', '\u{0301}', '\u{030C}', '\u{0300}'][tone as usize - 1];
+    format!("{}{}", yale, mark)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinyin_to_yale() {
+        let cases = [
+            ("zhong1", "jung1"),
+            ("xiang4", "syang4"),
+            ("qu4", "chyu4"),
+            ("nv3", "nyu3"),
+            ("xue2", "sywe2"),
+            ("jia1", "jya1"),
+            ("zhi4", "jr4"),
+            ("zi3", "dz3"),
+            ("ci2", "tsz2"),
+            ("hao3", "hau3"),
+            ("dui4", "dwei4"),
+            ("dun1", "dwun1"),
+            ("liu2", "lyou2"),
+            ("bo2", "bwo2"),
+            ("xiong2", "syung2"),
+            ("yue4", "ywe4"),
+            ("yuan2", "ywan2"),
+            ("you3", "you3"),
+            ("yi1", "yi1"),
+            ("yo1", "yo1"),
+            ("wen2", "wen2"),
+            ("wu3", "wu3"),
+            ("er2", "er2"),
+            ("ma5", "ma5"),
+        ];
+        for (pinyin, yale) in cases {
+            assert_eq!(pinyin_to_yale(pinyin).as_deref(), Some(yale), "{}", pinyin);
+        }
+    }
+
+    #[test]
+    fn test_pinyin_to_yale_tone_marks() {
+        assert_eq!(pinyin_to_yale("xiàng").as_deref(), Some("syàng"));
+        assert_eq!(pinyin_to_yale("hǎo").as_deref(), Some("hǎu"));
+        assert_eq!(pinyin_to_yale("shì").as_deref(), Some("shr\u{0300}"));
+        assert_eq!(pinyin_to_yale("ma").as_deref(), Some("ma"));
+        assert_eq!(pinyin_to_yale("Zhong").as_deref(), Some("jung"));
+        assert_eq!(pinyin_to_yale("xyz1"), None);
+    }
+}